@@ -1,34 +1,72 @@
 use eyre::{Result, ensure};
 use rand::distr::uniform::SampleRange;
+use std::fmt::Display;
+
+use crate::scalar::Scalar;
 
 /// Represents an interval with a start and end value.
 /// The interval is inclusive of start and exclusive of end.
+/// Zero-width intervals are not allowed, as `[x, x)` would contain nothing, use
+/// [Interval::containing_only] for the narrowest interval holding a single value.
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub struct Interval {
-    start: f64,
-    end: f64,
+pub struct Interval<F = f64> {
+    start: F,
+    end: F,
 }
 
-impl Interval {
-    pub fn try_new(start: f64, end: f64) -> Result<Self> {
-        ensure!(start < end, "Start must be less to end");
+impl<F: Scalar> Display for Interval<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}, {})", self.start, self.end)
+    }
+}
+
+impl<F: Scalar> Interval<F> {
+    /// Errors unless `start < end` and both are finite, so a zero-width `[x, x)` is rejected
+    pub fn try_new(start: F, end: F) -> Result<Self> {
+        ensure!(
+            !start.is_nan() && !end.is_nan(),
+            "Start and end must not be NaN"
+        );
+        ensure!(start < end, "Start must be less than end");
         ensure!(start.is_finite(), "Start must be finite");
         ensure!(end.is_finite(), "End must be finite");
         Ok(Interval { start, end })
     }
 
-    pub fn start(&self) -> &f64 {
+    /// The narrowest interval `[value, next_up(value))`, which contains exactly `value`.
+    /// Errors if `value` is not finite, or is the largest finite value.
+    pub fn containing_only(value: F) -> Result<Self> {
+        Interval::try_new(value, value.next_up())
+    }
+
+    pub fn start(&self) -> &F {
         &self.start
     }
 
-    pub fn end(&self) -> &f64 {
+    pub fn end(&self) -> &F {
         &self.end
     }
 
-    pub fn contains(&self, value: &f64) -> bool {
+    /// Half-open containment: `start <= value < end`
+    pub fn contains(&self, value: &F) -> bool {
         self.start <= *value && *value < self.end
     }
 
+    /// Closed containment: `start <= value <= end`
+    pub fn contains_inclusive(&self, value: &F) -> bool {
+        self.start <= *value && *value <= self.end
+    }
+
+    /// Whether `other` lies entirely within this Interval: `start <= other.start && other.end <= end`
+    pub fn contains_interval(&self, other: &Self) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// The width of the Interval, `end - start`
+    pub fn length(&self) -> F {
+        self.end - self.start
+    }
+
     /// Subdivides the Interval at the mid-point
     pub fn subdivide(&self) -> Vec<Self> {
         let midpoint = self.start.midpoint(self.end);
@@ -51,15 +89,43 @@ impl Interval {
     pub fn intersects(&self, other: &Self) -> bool {
         self.start < other.end && other.start < self.end
     }
+
+    /// Like [Interval::intersects], but with this Interval closed, `[start, end]`, so `other`
+    /// starting exactly on `end` still overlaps. See [Interval::contains_inclusive].
+    pub fn intersects_inclusive(&self, other: &Self) -> bool {
+        self.start < other.end && other.start <= self.end
+    }
+
+    /// Returns the smallest Interval covering both, even if they don't overlap
+    pub fn merge(&self, other: &Self) -> Self {
+        Interval {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    /// Returns the overlapping Interval, or None if the intervals are disjoint or only touch
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        self.intersects(other).then(|| Interval {
+            start: self.start.max(other.start),
+            end: self.end.min(other.end),
+        })
+    }
 }
 
-impl SampleRange<f64> for Interval {
+impl<F: Scalar> SampleRange<F> for Interval<F> {
     fn sample_single<R: rand::RngCore + ?Sized>(
         self,
         rng: &mut R,
-    ) -> std::result::Result<f64, rand::distr::uniform::Error> {
-        // Generate a random value within the interval
-        (self.start..=self.end).sample_single(rng)
+    ) -> std::result::Result<F, rand::distr::uniform::Error> {
+        // Generate a random value within the half-open interval. Rounding can make the float
+        // sampler return `end` itself, which `contains` excludes, so such values are redrawn.
+        loop {
+            let value = (self.start..self.end).sample_single(rng)?;
+            if value < self.end {
+                return Ok(value);
+            }
+        }
     }
 
     fn is_empty(&self) -> bool {
@@ -90,6 +156,46 @@ mod tests {
         assert!(invalid_interval.is_err());
     }
 
+    #[test]
+    fn test_interval_display() -> Result<()> {
+        assert_eq!(Interval::try_new(1.0, 5.0)?.to_string(), "[1, 5)");
+        assert_eq!(Interval::try_new(-0.5, 2.25)?.to_string(), "[-0.5, 2.25)");
+        Ok(())
+    }
+
+    #[test]
+    fn test_interval_nan() {
+        let nan_start = Interval::try_new(f64::NAN, 1.0).unwrap_err();
+        assert_eq!(nan_start.to_string(), "Start and end must not be NaN");
+        let nan_end = Interval::try_new(1.0, f64::NAN).unwrap_err();
+        assert_eq!(nan_end.to_string(), "Start and end must not be NaN");
+    }
+
+    #[test]
+    fn test_interval_contains_inclusive() {
+        let interval = Interval::try_new(1.0, 5.0).unwrap();
+        // The upper bound is only contained in inclusive mode
+        assert!(!interval.contains(&5.0));
+        assert!(interval.contains_inclusive(&5.0));
+        assert!(interval.contains_inclusive(&1.0));
+        assert!(!interval.contains_inclusive(&5.1));
+    }
+
+    #[test]
+    fn test_interval_contains_interval() {
+        let outer = Interval::try_new(0.0, 10.0).unwrap();
+        assert!(outer.contains_interval(&outer));
+        assert!(outer.contains_interval(&Interval::try_new(2.0, 5.0).unwrap()));
+        assert!(outer.contains_interval(&Interval::try_new(5.0, 10.0).unwrap()));
+        assert!(!outer.contains_interval(&Interval::try_new(5.0, 11.0).unwrap()));
+        assert!(!outer.contains_interval(&Interval::try_new(-1.0, 5.0).unwrap()));
+        assert!(
+            !Interval::try_new(2.0, 5.0)
+                .unwrap()
+                .contains_interval(&outer)
+        );
+    }
+
     #[test]
     fn test_interval_subdivide() {
         let interval = Interval::try_new(1.0, 5.0).unwrap();
@@ -104,6 +210,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_interval_zero_width() {
+        // [x, x) would be empty, so it is rejected rather than silently containing nothing
+        assert!(Interval::try_new(1.0, 1.0).is_err());
+        assert!(Interval::try_new(0.0, -0.0).is_err());
+
+        // The narrowest legal interval holds exactly one value
+        let interval = Interval::containing_only(1.0).unwrap();
+        assert!(interval.contains(&1.0));
+        assert!(!interval.contains(&1.0f64.next_up()));
+        assert!(!interval.contains(&1.0f64.next_down()));
+        assert!(Interval::containing_only(f64::MAX).is_err());
+        assert!(Interval::containing_only(f64::NAN).is_err());
+
+        // and can't be subdivided any further
+        assert_eq!(interval.subdivide(), vec![interval]);
+    }
+
     #[test]
     fn test_interval_subdivide_epsilon() {
         // Trying to subdivide something that cannot be divided should only yield itself
@@ -130,6 +254,71 @@ mod tests {
         assert!(!interval_d.intersects(&interval_a));
     }
 
+    #[test]
+    fn test_interval_intersects_inclusive() {
+        let interval_a = Interval::try_new(1.0, 5.0).unwrap();
+        let interval_b = Interval::try_new(5.0, 7.0).unwrap();
+        // Only the closed end of a touches b
+        assert!(interval_a.intersects_inclusive(&interval_b));
+        assert!(!interval_b.intersects_inclusive(&interval_a));
+        assert!(!interval_a.intersects(&interval_b));
+
+        let interval_c = Interval::try_new(5.5, 7.0).unwrap();
+        assert!(!interval_a.intersects_inclusive(&interval_c));
+    }
+
+    #[test]
+    fn test_interval_intersection() {
+        let interval_a = Interval::try_new(1.0, 5.0).unwrap();
+
+        // Partial overlap
+        let interval_b = Interval::try_new(4.0, 6.0).unwrap();
+        assert_eq!(
+            interval_a.intersection(&interval_b),
+            Some(Interval::try_new(4.0, 5.0).unwrap())
+        );
+
+        // Containment
+        let interval_c = Interval::try_new(2.0, 3.0).unwrap();
+        assert_eq!(interval_a.intersection(&interval_c), Some(interval_c));
+
+        // Touching and disjoint
+        let interval_d = Interval::try_new(5.0, 7.0).unwrap();
+        let interval_e = Interval::try_new(8.0, 9.0).unwrap();
+        assert_eq!(interval_a.intersection(&interval_d), None);
+        assert_eq!(interval_a.intersection(&interval_e), None);
+    }
+
+    #[test]
+    fn test_interval_merge() {
+        let interval_a = Interval::try_new(1.0, 5.0).unwrap();
+
+        // Overlapping
+        let interval_b = Interval::try_new(4.0, 6.0).unwrap();
+        assert_eq!(
+            interval_a.merge(&interval_b),
+            Interval::try_new(1.0, 6.0).unwrap()
+        );
+
+        // Disjoint, the gap is covered too
+        let interval_c = Interval::try_new(8.0, 9.0).unwrap();
+        assert_eq!(
+            interval_c.merge(&interval_a),
+            Interval::try_new(1.0, 9.0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_interval_sample_range_half_open() {
+        // The narrowest possible interval only contains its start
+        let interval = Interval::try_new(1.0, 1.0 + f64::EPSILON).unwrap();
+        let mut rng = rand::rng();
+        for _ in 0..1000 {
+            let sample = interval.sample_single(&mut rng).unwrap();
+            assert_eq!(sample, 1.0);
+        }
+    }
+
     #[test]
     fn test_interval_sample_range() {
         let interval = Interval::try_new(1.0, 5.0).unwrap();
@@ -3,5 +3,7 @@ pub mod point;
 pub mod quadtree;
 pub mod query;
 pub mod region;
+pub mod scalar;
+pub mod subdivision;
 
-pub use quadtree::QuadTree;
+pub use quadtree::{DynQuadTree, QuadTree, QuadTree2};
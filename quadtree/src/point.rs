@@ -1,17 +1,56 @@
-use std::fmt::Display;
-
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    ops::{Add, Sub},
+};
+
+use approx::abs_diff_eq;
 use eyre::{Result, ensure};
 use itertools::Itertools;
 use ordered_float::OrderedFloat;
 use serdev::{Deserialize, Serialize};
 
-use crate::{quadtree::Storable, query::DistanceQuery};
+use crate::{quadtree::Storable, query::DistanceQuery, scalar::Scalar};
 
 /// Point represents a point in n-dimensional space.
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-pub struct Point<const N: usize>([OrderedFloat<f64>; N]);
+/// Coordinates are [f64] unless another [Scalar] is given, e.g. `Point<2, f32>`.
+#[derive(Copy, Clone, Debug)]
+pub struct Point<const N: usize, F = f64>([OrderedFloat<F>; N]);
+
+// Equality, ordering and hashing follow OrderedFloat, so they are implemented by hand rather than
+// derived, which would require them of the (float) scalar itself
+
+impl<const N: usize, F: Scalar> PartialEq for Point<N, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<const N: usize, F: Scalar> Eq for Point<N, F> {}
+
+impl<const N: usize, F: Scalar> PartialOrd for Point<N, F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize, F: Scalar> Ord for Point<N, F> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl<const N: usize, F: Scalar> Hash for Point<N, F>
+where
+    OrderedFloat<F>: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
 
-impl<const N: usize> Display for Point<N> {
+impl<const N: usize, F: Scalar> Display for Point<N, F> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
@@ -21,13 +60,53 @@ impl<const N: usize> Display for Point<N> {
     }
 }
 
-impl<const N: usize> Default for Point<N> {
+impl<const N: usize, F: Scalar> Default for Point<N, F> {
     fn default() -> Self {
-        Self([OrderedFloat(0.0); N])
+        Self([OrderedFloat(F::zero()); N])
+    }
+}
+
+impl<const N: usize, F: Scalar> Add for Point<N, F> {
+    type Output = Point<N, F>;
+
+    /// Component-wise addition
+    fn add(self, other: Point<N, F>) -> Point<N, F> {
+        Point(std::array::from_fn(|i| self.0[i] + other.0[i]))
+    }
+}
+
+impl<const N: usize, F: Scalar> Sub for Point<N, F> {
+    type Output = Point<N, F>;
+
+    /// Component-wise subtraction
+    fn sub(self, other: Point<N, F>) -> Point<N, F> {
+        Point(std::array::from_fn(|i| self.0[i] - other.0[i]))
+    }
+}
+
+impl<const N: usize, F: Scalar> From<[F; N]> for Point<N, F> {
+    /// Like [Point::new], values are not checked for finiteness
+    fn from(values: [F; N]) -> Self {
+        Point(values.map(OrderedFloat))
+    }
+}
+
+impl<const N: usize, F: Scalar> TryFrom<Vec<F>> for Point<N, F> {
+    type Error = eyre::Report;
+
+    /// Errors under the same conditions as [Point::try_new]
+    fn try_from(values: Vec<F>) -> Result<Self> {
+        Point::try_from_scalars(&values)
     }
 }
 
-impl<const N: usize> Serialize for Point<N> {
+impl<const N: usize, F: Scalar> From<Point<N, F>> for [F; N] {
+    fn from(point: Point<N, F>) -> Self {
+        point.to_array()
+    }
+}
+
+impl<const N: usize, F: Scalar + Serialize> Serialize for Point<N, F> {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: serdev::Serializer,
@@ -36,13 +115,13 @@ impl<const N: usize> Serialize for Point<N> {
     }
 }
 
-impl<'de, const N: usize> Deserialize<'de> for Point<N> {
+impl<'de, const N: usize, F: Scalar + Deserialize<'de>> Deserialize<'de> for Point<N, F> {
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
     where
         D: serdev::Deserializer<'de>,
     {
-        let values: Vec<OrderedFloat<f64>> = Deserialize::deserialize(deserializer)?;
-        let array: [OrderedFloat<f64>; N] = values
+        let values: Vec<OrderedFloat<F>> = Deserialize::deserialize(deserializer)?;
+        let array: [OrderedFloat<F>; N] = values
             .try_into()
             .map_err(|_| serdev::de::Error::custom(format!("Expected a Vec of size {}", N)))?;
         Ok(Point(array))
@@ -52,6 +131,10 @@ impl<'de, const N: usize> Deserialize<'de> for Point<N> {
 impl<const N: usize> Point<N> {
     /// Create a new [Point] from a slice of values
     /// Panics if the length of the slice is not equal to N
+    ///
+    /// Values are not checked for finiteness: a NaN or infinite coordinate is stored as given,
+    /// and will silently misbehave in distances and containment. Use [Point::try_new] for
+    /// untrusted input.
     pub fn new<T: Copy + Into<f64>>(values: &[T; N]) -> Point<N> {
         Point(
             values
@@ -62,55 +145,200 @@ impl<const N: usize> Point<N> {
         )
     }
 
-    pub fn try_new<T: Copy + Into<f64>>(vec: &[T]) -> Result<Point<N>> {
+    /// Create a new [Point] from a slice of any length, erroring if it is not N
+    /// or if any value is NaN or infinite
+    pub fn try_new<T: Copy + Into<f64>>(values: &[T]) -> Result<Point<N>> {
         ensure!(
-            vec.len() == N,
-            "cannot create point of size {} from Vec of size {}",
+            values.len() == N,
+            "cannot create point of size {} from slice of size {}",
             N,
-            &vec.len()
+            &values.len()
+        );
+        ensure!(
+            values.iter().all(|value| (*value).into().is_finite()),
+            "cannot create point with non-finite values"
         );
 
         Ok(Point(
-            vec.iter()
+            values
+                .iter()
                 .cloned()
                 .map(|value| OrderedFloat((value).into()))
                 .collect_array()
                 .expect("same sized array"),
         ))
     }
+}
 
-    pub fn dimension_values(&self) -> &[f64; N] {
-        // SAFETY: OrderedFloat<f64> has the same repr as f64, and the array sizes match.
-        unsafe { &*(self.0.as_ptr() as *const [f64; N]) }
+impl<const N: usize, F: Scalar> Point<N, F> {
+    /// Create a new [Point] from an array of scalar values, like [Point::new] but for any
+    /// [Scalar], e.g. `Point::<2, f32>::from_scalars(&[1.0, 2.0])`.
+    /// Values are not checked for finiteness, use [Point::try_from_scalars] for untrusted input.
+    pub fn from_scalars(values: &[F; N]) -> Point<N, F> {
+        Point::from(*values)
+    }
+
+    /// Create a new [Point] from a slice of scalar values, like [Point::try_new] but for any
+    /// [Scalar]. Errors if the slice is not of length N, or if any value is NaN or infinite.
+    pub fn try_from_scalars(values: &[F]) -> Result<Point<N, F>> {
+        ensure!(
+            values.len() == N,
+            "cannot create point of size {} from slice of size {}",
+            N,
+            values.len()
+        );
+        ensure!(
+            values.iter().all(|value| value.is_finite()),
+            "cannot create point with non-finite values"
+        );
+
+        Ok(Point::from(
+            <[F; N]>::try_from(values).expect("same sized array"),
+        ))
+    }
+
+    /// Create a new [Point] from any iterator of scalar values, e.g. parsed or mapped on the fly,
+    /// without collecting them first. Errors under the same conditions as [Point::try_new].
+    pub fn from_values<I: IntoIterator<Item = F>>(values: I) -> Result<Point<N, F>> {
+        let mut array = [F::zero(); N];
+        let mut count = 0;
+        for value in values {
+            ensure!(
+                count < N,
+                "cannot create point of size {} from more than {} values",
+                N,
+                N
+            );
+            array[count] = value;
+            count += 1;
+        }
+        ensure!(
+            count == N,
+            "cannot create point of size {} from {} values",
+            N,
+            count
+        );
+        Point::try_from_scalars(&array)
+    }
+
+    /// Borrow the coordinates as plain scalar values, without copying.
+    /// Relies on [OrderedFloat] being `#[repr(transparent)]`, which is checked at compile time,
+    /// see [Point::to_array] for a copying alternative that doesn't.
+    pub fn dimension_values(&self) -> &[F; N] {
+        // Fails the build, rather than misreading memory, should OrderedFloat's layout change
+        const {
+            assert!(size_of::<[OrderedFloat<F>; N]>() == size_of::<[F; N]>());
+            assert!(align_of::<[OrderedFloat<F>; N]>() == align_of::<[F; N]>());
+        }
+        // SAFETY: OrderedFloat<F> is #[repr(transparent)] over F, so [OrderedFloat<F>; N]
+        // has the same size, alignment and layout as [F; N], and every F bit pattern is valid.
+        unsafe { &*(self.0.as_ptr() as *const [F; N]) }
+    }
+
+    /// Copy the coordinates out as an owned array, without the layout assumption of
+    /// [Point::dimension_values]
+    pub fn to_array(&self) -> [F; N] {
+        self.0.map(|value| value.0)
     }
 
     pub fn dimensions(&self) -> usize {
         self.0.len()
     }
 
-    pub fn distance(&self, other: &Point<N>) -> f64 {
-        if self.0.len() != other.0.len() {
-            panic!("Points must have the same dimension");
-        }
-        self.0
+    pub fn distance(&self, other: &Point<N, F>) -> F {
+        self.distance_squared(other).sqrt()
+    }
+
+    /// Squared Euclidean distance, avoiding the `sqrt`.
+    /// Sufficient for comparing distances against each other or a squared threshold.
+    pub fn distance_squared(&self, other: &Point<N, F>) -> F {
+        self.dimension_values()
             .iter()
-            .zip(other.0.iter())
-            .map(|(a, b)| {
-                let diff = a - b;
-                (diff * diff).0
-            })
-            .sum::<f64>()
-            .sqrt()
+            .zip(other.dimension_values())
+            .map(|(&a, &b)| (a - b) * (a - b))
+            .sum()
+    }
+
+    /// Whether every dimension is within `epsilon` of the other's, for points computed
+    /// in ways (midpoints, lerps, centroids) that rarely match exactly
+    pub fn approx_eq(&self, other: &Point<N, F>, epsilon: F) -> bool {
+        self.dimension_values()
+            .iter()
+            .zip(other.dimension_values())
+            .all(|(a, b)| abs_diff_eq!(a, b, epsilon = epsilon))
+    }
+
+    /// Absolute separation between two points along a single dimension
+    pub fn axis_distance(&self, other: &Point<N, F>, dimension: usize) -> Result<F> {
+        ensure!(
+            dimension < N,
+            "dimension {} out of range for point of size {}",
+            dimension,
+            N
+        );
+        Ok((self.0[dimension].0 - other.0[dimension].0).abs())
+    }
+
+    /// Multiply every coordinate by `factor`
+    pub fn scale(&self, factor: F) -> Point<N, F> {
+        Point(self.0.map(|value| OrderedFloat(value.0 * factor)))
     }
 
-    pub fn to_distance_based_query(&self, distance: f64) -> DistanceQuery<N> {
+    /// The point halfway between `self` and `other`
+    pub fn midpoint(&self, other: &Point<N, F>) -> Point<N, F> {
+        Point(std::array::from_fn(|i| {
+            OrderedFloat(self.0[i].0.midpoint(other.0[i].0))
+        }))
+    }
+
+    /// Linear interpolation from `self` (t = 0) to `other` (t = 1).
+    /// `t` is not clamped, values outside [0, 1] extrapolate along the same line.
+    pub fn lerp(&self, other: &Point<N, F>, t: F) -> Point<N, F> {
+        *self + (*other - *self).scale(t)
+    }
+
+    pub fn to_distance_based_query(&self, distance: F) -> DistanceQuery<N, F> {
         DistanceQuery::new(self, distance)
     }
 }
 
+/// The common 2D case, `Point2` rather than `Point<2>`
+pub type Point2 = Point<2>;
+
+impl<F: Scalar> Point<2, F> {
+    /// Create a 2D [Point] from its coordinates, e.g. `Point2::xy(1.0, 2.0)`.
+    /// Like [Point::new], values are not checked for finiteness.
+    pub fn xy(x: F, y: F) -> Self {
+        Point::from([x, y])
+    }
+}
+
+/// Distances from every point to the target, in one pass
+pub fn distances_to<const N: usize, F: Scalar>(
+    points: &[Point<N, F>],
+    target: &Point<N, F>,
+) -> Vec<F> {
+    distances_squared_to(points, target)
+        .into_iter()
+        .map(F::sqrt)
+        .collect()
+}
+
+/// Squared distances from every point to the target, avoiding the `sqrt`.
+/// Sufficient for ranking points by distance.
+pub fn distances_squared_to<const N: usize, F: Scalar>(
+    points: &[Point<N, F>],
+    target: &Point<N, F>,
+) -> Vec<F> {
+    points
+        .iter()
+        .map(|point| point.distance_squared(target))
+        .collect()
+}
+
 /// We can trivialy implement [Storable] for [Point]
-impl<const N: usize> Storable<Point<N>, N> for Point<N> {
-    fn point(&self) -> &Point<N> {
+impl<const N: usize, F: Scalar> Storable<Point<N, F>, N, F> for Point<N, F> {
+    fn point(&self) -> &Point<N, F> {
         self
     }
 
@@ -119,6 +347,35 @@ impl<const N: usize> Storable<Point<N>, N> for Point<N> {
     }
 }
 
+#[cfg(feature = "nalgebra")]
+impl<const N: usize> From<Point<N>> for nalgebra::SVector<f64, N> {
+    fn from(point: Point<N>) -> Self {
+        nalgebra::SVector::from(point.to_array())
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<const N: usize> From<nalgebra::SVector<f64, N>> for Point<N> {
+    fn from(vector: nalgebra::SVector<f64, N>) -> Self {
+        Point::from(<[f64; N]>::from(vector))
+    }
+}
+
+#[cfg(feature = "geo")]
+impl From<Point<2>> for geo::Point<f64> {
+    fn from(point: Point<2>) -> Self {
+        let [x, y] = point.to_array();
+        geo::Point::new(x, y)
+    }
+}
+
+#[cfg(feature = "geo")]
+impl From<geo::Point<f64>> for Point<2> {
+    fn from(point: geo::Point<f64>) -> Self {
+        Point::from([point.x(), point.y()])
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -143,6 +400,72 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_point_try_new_from_slices() {
+        let array = [1.0, 2.0];
+        let vec = vec![1.0, 2.0];
+        let from_array = Point::<2>::try_new(&array[..]).unwrap();
+        let from_vec = Point::<2>::try_new(&vec).unwrap();
+        assert_eq!(from_array, from_vec);
+
+        // Length mismatch is still an error
+        assert!(Point::<3>::try_new(vec.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_point_try_new_non_finite() {
+        assert!(Point::<2>::try_new(&[f64::NAN, 1.0]).is_err());
+        assert!(Point::<2>::try_new(&[1.0, f64::INFINITY]).is_err());
+        assert!(Point::<2>::try_new(&[f64::NEG_INFINITY, 1.0]).is_err());
+        assert!(Point::<2>::try_new(&[f64::MAX, f64::MIN]).is_ok());
+    }
+
+    #[test]
+    fn test_point_from_values() {
+        let coordinates = ["1.5", "2"];
+        let point = Point::<2>::from_values(coordinates.iter().map(|value| value.parse().unwrap()))
+            .unwrap();
+        assert_eq!(point, Point::new(&[1.5, 2.0]));
+
+        assert!(Point::<3>::from_values((0..2).map(f64::from)).is_err());
+        assert!(Point::<2>::from_values((0..3).map(f64::from)).is_err());
+        // An endless iterator errors as soon as it yields too many, rather than running forever
+        assert!(Point::<2>::from_values(std::iter::repeat(1.0)).is_err());
+        assert!(Point::<2>::from_values([1.0, f64::NAN]).is_err());
+    }
+
+    #[test]
+    fn test_point_conversions() {
+        let point: Point<3> = [1.0, 2.0, 3.0].into();
+        assert_eq!(point, Point::new(&[1.0, 2.0, 3.0]));
+
+        let array: [f64; 3] = point.into();
+        assert_eq!(array, [1.0, 2.0, 3.0]);
+
+        let from_vec = Point::<3>::try_from(vec![1.0, 2.0, 3.0]).unwrap();
+        assert_eq!(from_vec, point);
+        assert!(Point::<3>::try_from(vec![1.0, 2.0]).is_err());
+        assert!(Point::<3>::try_from(vec![1.0, 2.0, 3.0, 4.0]).is_err());
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn test_point_nalgebra_round_trip() {
+        let point = Point::new(&[1.0, -2.5, 3.0]);
+        let vector: nalgebra::SVector<f64, 3> = point.into();
+        assert_eq!(vector, nalgebra::Vector3::new(1.0, -2.5, 3.0));
+        assert_eq!(Point::from(vector), point);
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn test_point_geo_round_trip() {
+        let point = Point::new(&[1.5, -2.0]);
+        let geo_point: geo::Point<f64> = point.into();
+        assert_eq!(geo_point, geo::Point::new(1.5, -2.0));
+        assert_eq!(Point::from(geo_point), point);
+    }
+
     #[test]
     fn test_point_creation_slices() {
         // 3D using integers
@@ -158,6 +481,34 @@ mod tests {
         assert_eq!(point_2d.0, [4.0, 5.0]);
     }
 
+    #[test]
+    fn test_point2_xy() {
+        let point = Point2::xy(1.5, -2.0);
+        assert_eq!(point, Point::new(&[1.5, -2.0]));
+        assert_eq!(point.dimension_values(), &[1.5, -2.0]);
+        assert_eq!(Point::xy(1.0f32, 2.0), Point::from([1.0f32, 2.0]));
+    }
+
+    #[test]
+    fn test_point_constructors_f32() {
+        let expected = Point::from([1.0f32, 2.0]);
+        let values = [1.0f32, 2.0];
+        assert_eq!(Point::from_scalars(&values), expected);
+        assert_eq!(Point::try_from_scalars(&values).unwrap(), expected);
+        assert_eq!(Point::from_values(values).unwrap(), expected);
+        assert_eq!(Point::try_from(values.to_vec()).unwrap(), expected);
+        assert!(Point::<2, f32>::try_from_scalars(&[1.0, f32::NAN]).is_err());
+        assert!(Point::<2, f32>::try_from_scalars(&[1.0]).is_err());
+    }
+
+    #[test]
+    fn test_point_new_widens_f32() {
+        // f32 values (and anything else Into<f64>) still build the default f64 point
+        let point = Point::<2>::new(&[1.0f32, 2.5]);
+        assert_eq!(point, Point::from([1.0, 2.5]));
+        assert_eq!(Point::<2>::try_new(&[1.0f32, 2.5]).unwrap(), point);
+    }
+
     #[test]
     fn test_point_distance() {
         let point_a = Point::new(&[1.0, 2.0, 3.0]);
@@ -166,6 +517,29 @@ mod tests {
         assert_abs_diff_eq!(distance, 5.2, epsilon = 0.01);
     }
 
+    #[test]
+    fn test_point_distance_squared() {
+        let point_a = Point::new(&[1.0, -2.0, 3.5]);
+        let point_b = Point::new(&[4.0, 5.0, -6.0]);
+        assert_abs_diff_eq!(
+            point_a.distance_squared(&point_b),
+            point_a.distance(&point_b).powi(2),
+            epsilon = 1e-9
+        );
+        assert_eq!(point_a.distance_squared(&point_a), 0.0);
+    }
+
+    #[test]
+    fn test_point_approx_eq() {
+        let start = Point::new(&[0.1, 0.7]);
+        let end = Point::new(&[0.3, 0.1]);
+        let lerped = start.lerp(&end, 0.7);
+        let expected = Point::new(&[0.24, 0.28]);
+        assert_ne!(lerped, expected);
+        assert!(lerped.approx_eq(&expected, 1e-12));
+        assert!(!lerped.approx_eq(&Point::new(&[0.24, 0.29]), 1e-12));
+    }
+
     #[test]
     fn test_point_distance_i32() {
         let point_a = Point::new(&[1, 2, 3]);
@@ -174,6 +548,67 @@ mod tests {
         assert_abs_diff_eq!(distance, 5.2, epsilon = 0.01);
     }
 
+    #[test]
+    fn test_point_axis_distance() {
+        let point_a = Point::new(&[1.0, 2.0, 3.0]);
+        let point_b = Point::new(&[4.0, -5.0, 3.0]);
+        assert_eq!(point_a.axis_distance(&point_b, 0).unwrap(), 3.0);
+        assert_eq!(point_a.axis_distance(&point_b, 1).unwrap(), 7.0);
+        assert_eq!(point_a.axis_distance(&point_b, 2).unwrap(), 0.0);
+
+        // Out of range dimension
+        assert!(point_a.axis_distance(&point_b, 3).is_err());
+    }
+
+    #[test]
+    fn test_distances_to() {
+        let target = Point::new(&[1.0, 2.0, 3.0]);
+        let points = vec![
+            Point::new(&[4.0, 5.0, 6.0]),
+            Point::new(&[1.0, 2.0, 3.0]),
+            Point::new(&[-1.0, 0.0, 10.0]),
+        ];
+        let distances = distances_to(&points, &target);
+        let squared = distances_squared_to(&points, &target);
+        assert_eq!(distances.len(), points.len());
+        for ((point, distance), squared) in points.iter().zip(&distances).zip(&squared) {
+            assert_abs_diff_eq!(*distance, point.distance(&target), epsilon = 1e-12);
+            assert_abs_diff_eq!(*squared, point.distance(&target).powi(2), epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_point_add_sub() {
+        let point_a = Point::new(&[1.0, 2.0, 3.0]);
+        let point_b = Point::new(&[4.0, 5.0, 6.5]);
+        assert_eq!(point_a + point_b, Point::new(&[5.0, 7.0, 9.5]));
+        assert_eq!(point_b - point_a, Point::new(&[3.0, 3.0, 3.5]));
+    }
+
+    #[test]
+    fn test_point_scale() {
+        let point = Point::new(&[1.5, -2.0]);
+        assert_eq!(point.scale(2.0), Point::new(&[3.0, -4.0]));
+    }
+
+    #[test]
+    fn test_point_midpoint() {
+        let point_a = Point::new(&[1.0, 2.0]);
+        let point_b = Point::new(&[5.0, -4.0]);
+        assert_eq!(point_a.midpoint(&point_b), Point::new(&[3.0, -1.0]));
+    }
+
+    #[test]
+    fn test_point_lerp() {
+        let point_a = Point::new(&[0.0, 10.0]);
+        let point_b = Point::new(&[8.0, 2.0]);
+        assert_eq!(point_a.lerp(&point_b, 0.0), point_a);
+        assert_eq!(point_a.lerp(&point_b, 1.0), point_b);
+        assert_eq!(point_a.lerp(&point_b, 0.25), Point::new(&[2.0, 8.0]));
+        // Extrapolation
+        assert_eq!(point_a.lerp(&point_b, 2.0), Point::new(&[16.0, -6.0]));
+    }
+
     #[test]
     fn test_display() {
         let point = Point::new(&[1.0, 2.1, 3.0]);
@@ -193,6 +628,28 @@ mod tests {
         assert_eq!(values, &[1.0, 2.0, 3.0]);
     }
 
+    #[test]
+    fn test_point_dimension_values_layout() {
+        // Exercises the pointer cast on fresh points of each scalar, so Miri can check it
+        let point = Point::from([-0.0f32, f32::MIN_POSITIVE, f32::MAX]);
+        assert_eq!(point.dimension_values(), &point.to_array());
+        let point = Point::from([f64::MIN, 0.1, 1e300, -7.5]);
+        assert_eq!(point.dimension_values(), &point.to_array());
+        assert!(Point::<0, f64>::default().dimension_values().is_empty());
+
+        assert_eq!(size_of::<OrderedFloat<f64>>(), size_of::<f64>());
+        assert_eq!(align_of::<OrderedFloat<f64>>(), align_of::<f64>());
+        assert_eq!(size_of::<OrderedFloat<f32>>(), size_of::<f32>());
+        assert_eq!(align_of::<OrderedFloat<f32>>(), align_of::<f32>());
+    }
+
+    #[test]
+    fn test_point_to_array() {
+        let point = Point::new(&[1.0, -2.5, 3.0]);
+        let array = point.to_array();
+        assert_eq!(&array, point.dimension_values());
+    }
+
     #[test]
     fn test_point_dimensions() {
         let point = Point::new(&[1.0, 2.0, 3.0]);
@@ -1,12 +1,110 @@
-use crate::{point::Point, query::Query, region::Region};
-use eyre::{OptionExt, Result, bail};
-use std::num::NonZero;
+use crate::{
+    interval::Interval,
+    point::Point,
+    query::{Containment, DistanceQuery, PointQuery, Query},
+    region::Region,
+    scalar::Scalar,
+    subdivision::{SplitAllAxes, SubdivisionStrategy},
+};
+use eyre::{OptionExt, Result, bail, ensure};
+use ordered_float::OrderedFloat;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::{any::Any, cmp::Ordering, collections::BinaryHeap, marker::PhantomData, num::NonZero};
 
-pub trait Storable<V, const N: usize> {
-    fn point(&self) -> &Point<N>;
+/// The maximum number of points per node used when none is given, e.g. by [FromIterator]
+pub const DEFAULT_MAX_POINTS: NonZero<usize> = NonZero::new(8).expect("8 is non-zero");
+
+pub trait Storable<V, const N: usize, F = f64> {
+    fn point(&self) -> &Point<N, F>;
     fn item(&self) -> &V;
 }
 
+/// A value stored alongside its [Point], for data that doesn't carry its own coordinates
+#[derive(Clone, PartialEq, Debug)]
+pub struct Entry<const N: usize, T, F: Scalar = f64> {
+    point: Point<N, F>,
+    value: T,
+}
+
+impl<const N: usize, T, F: Scalar> Entry<N, T, F> {
+    pub fn new(point: Point<N, F>, value: T) -> Self {
+        Self { point, value }
+    }
+
+    pub fn point(&self) -> &Point<N, F> {
+        &self.point
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<const N: usize, T, F: Scalar> Storable<Entry<N, T, F>, N, F> for Entry<N, T, F> {
+    fn point(&self) -> &Point<N, F> {
+        &self.point
+    }
+
+    fn item(&self) -> &Self {
+        self
+    }
+}
+
+/// `(point, value)` pairs can be stored directly, without a wrapper
+impl<const N: usize, T, F> Storable<(Point<N, F>, T), N, F> for (Point<N, F>, T) {
+    fn point(&self) -> &Point<N, F> {
+        &self.0
+    }
+
+    fn item(&self) -> &Self {
+        self
+    }
+}
+
+/// An object safe view of a [Storable], so items of different concrete types can share a
+/// [DynQuadTree]. Implemented for every `'static` type that is [Storable] as itself.
+pub trait DynStorable<const N: usize, F = f64>: Any {
+    fn location(&self) -> &Point<N, F>;
+
+    /// The item as [Any]. Prefer `downcast_ref` on `dyn DynStorable`, as calling this on a
+    /// `Box<dyn DynStorable>` gives the box itself rather than the boxed item.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<const N: usize, F: 'static> dyn DynStorable<N, F> {
+    /// The item as its concrete type `T`, if it is one
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+}
+
+impl<const N: usize, F, T: Storable<T, N, F> + Any> DynStorable<N, F> for T {
+    fn location(&self) -> &Point<N, F> {
+        self.point()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl<const N: usize, F: Scalar> Storable<Box<dyn DynStorable<N, F>>, N, F>
+    for Box<dyn DynStorable<N, F>>
+{
+    fn point(&self) -> &Point<N, F> {
+        self.as_ref().location()
+    }
+
+    fn item(&self) -> &Self {
+        self
+    }
+}
+
+/// A [QuadTree] of boxed [DynStorable] items, which may each be a different concrete type
+pub type DynQuadTree<const N: usize, F = f64> =
+    QuadTree<N, Box<dyn DynStorable<N, F>>, SplitAllAxes, F>;
+
 /// Technically an 'orthree' this QuadTree struct is actually a generalised version
 /// of a quadtree that can be used for any number of dimensions.
 /// See <https://en.wikipedia.org/wiki/Quadtree> for more information.
@@ -49,315 +147,2779 @@ pub trait Storable<V, const N: usize> {
 /// #     Ok(())
 /// # }
 /// ```
+///
+/// Nodes split with [SplitAllAxes] by default, another [SubdivisionStrategy] can be chosen
+/// with [QuadTreeBuilder::strategy].
 #[derive(Clone, PartialEq, Debug)]
-pub struct QuadTree<const N: usize, V> {
-    region: Region<N>,
-    subtrees: Option<Vec<QuadTree<N, V>>>,
+pub struct QuadTree<const N: usize, V, S = SplitAllAxes, F = f64> {
+    region: Region<N, F>,
+    subtrees: Option<Vec<QuadTree<N, V, S, F>>>,
     points: Vec<V>,
+    max_points: NonZero<usize>,
+    max_depth: Option<NonZero<usize>>,
+    depth: usize,
+    auto_grow: bool,
+    leaves_only: bool,
+    strategy: PhantomData<fn() -> S>,
 }
 
-impl<const N: usize, V: Storable<V, N>> QuadTree<N, V> {
+impl<const N: usize, F: Scalar, V: Storable<V, N, F>> QuadTree<N, V, SplitAllAxes, F> {
     /// Create a new [QuadTree] with the given region and maximum number of points.
-    pub fn new(region: &Region<N>, max_points: NonZero<usize>) -> Self {
+    pub fn new(region: &Region<N, F>, max_points: NonZero<usize>) -> Self {
+        QuadTree::empty(region, max_points)
+    }
+
+    /// Create a new [QuadTree] that never subdivides deeper than `max_depth` (the root being depth 1).
+    /// Nodes at the maximum depth keep accepting points beyond `max_points` instead of subdividing,
+    /// which avoids endless subdivision when many points share (nearly) the same coordinates.
+    pub fn with_max_depth(
+        region: &Region<N, F>,
+        max_points: NonZero<usize>,
+        max_depth: NonZero<usize>,
+    ) -> Self {
+        QuadTree {
+            max_depth: Some(max_depth),
+            ..QuadTree::new(region, max_points)
+        }
+    }
+}
+
+/// The common 2D case, `QuadTree2<V>` rather than `QuadTree<2, V>`
+pub type QuadTree2<V> = QuadTree<2, V>;
+
+impl<F: Scalar, V: Storable<V, 2, F>> QuadTree<2, V, SplitAllAxes, F> {
+    /// Create a new 2D [QuadTree] over `[min_x, max_x) x [min_y, max_y)`, without building
+    /// the [Interval]s by hand. Errors if a minimum isn't below its maximum.
+    pub fn from_bounds(
+        min_x: F,
+        min_y: F,
+        max_x: F,
+        max_y: F,
+        max_points: NonZero<usize>,
+    ) -> Result<Self> {
+        let region = Region::new(&[
+            Interval::try_new(min_x, max_x)?,
+            Interval::try_new(min_y, max_y)?,
+        ]);
+        Ok(QuadTree::new(&region, max_points))
+    }
+}
+
+impl<const N: usize, F: Scalar, V: Storable<V, N, F>, S: SubdivisionStrategy<N, F>>
+    QuadTree<N, V, S, F>
+{
+    /// An empty root node, for any strategy
+    fn empty(region: &Region<N, F>, max_points: NonZero<usize>) -> Self {
         QuadTree {
             region: region.clone(),
             subtrees: None,
             points: Vec::with_capacity(max_points.into()),
+            max_points,
+            max_depth: None,
+            depth: 1,
+            auto_grow: false,
+            leaves_only: true,
+            strategy: PhantomData,
         }
     }
 
     /// Try to insert a point into the [QuadTree]. If the point is outside the quadtree's region, an error is returned.
     /// All points must be [Storable] and of the type set in the [QuadTree].
+    /// Unlike [Region::contains], the upper edge of the quadtree's region is accepted, so points
+    /// exactly on the region's maximum bounds can be stored.
+    /// Trees built with [QuadTreeBuilder::auto_grow] grow instead, see [QuadTree::insert_growing].
     pub fn insert(&mut self, point: V) -> Result<()> {
-        if self.points.len() < self.points.capacity() {
-            if !self.region.contains(point.point()) {
-                bail!("Point is outside the region");
-            }
+        if self.auto_grow {
+            return self.insert_growing(point);
+        }
+        self.insert_node(point, None)
+    }
+
+    /// Insert like [QuadTree::insert], returning the index of the child taken at each level
+    /// from the root down to the node the point landed in, see [QuadTree::children].
+    /// An empty path means the point was stored in the root. Useful for diagnosing deep
+    /// or lopsided subdivision.
+    pub fn insert_tracked(&mut self, point: V) -> Result<Vec<usize>> {
+        if self.auto_grow {
+            self.grow_to_fit(point.point())?;
+        }
+        let mut path = Vec::new();
+        self.insert_node(point, Some(&mut path))?;
+        Ok(path)
+    }
+
+    /// Insert into this node or the subtree the point belongs in, recording the child indices
+    /// taken in `path` if given
+    fn insert_node(&mut self, point: V, mut path: Option<&mut Vec<usize>>) -> Result<()> {
+        if !self.region.contains_inclusive(point.point()) {
+            bail!("Point is outside the region");
+        }
+
+        // Internal nodes of a leaves only tree route every point to their subtrees
+        let routing = self.leaves_only && self.subtrees.is_some();
+        if !routing && (self.points.len() < self.max_points.get() || self.at_max_depth()) {
             self.points.push(point);
             return Ok(());
         }
 
         if self.subtrees.is_none() {
+            // Subdividing can't separate coincident points, or split a region too small to
+            // halve, so keep overflowing this leaf instead of recursing. A maximum depth
+            // already bounds the recursion, so coincident points then subdivide down to it.
+            let coincident = self
+                .points
+                .iter()
+                .all(|stored| stored.point() == point.point());
+            if (coincident && self.max_depth.is_none()) || !self.can_subdivide() {
+                self.points.push(point);
+                return Ok(());
+            }
             self.subdivide();
+            if self.leaves_only {
+                self.push_points_down()?;
+            }
         }
 
-        for subtree in self
+        let subtrees = self
             .subtrees
             .as_mut()
-            .ok_or_eyre("subtrees not created, this is a bug")?
+            .ok_or_eyre("subtrees not created, this is a bug")?;
+        match Self::child_index(subtrees, point.point()) {
+            Some(index) => {
+                if let Some(path) = path.as_deref_mut() {
+                    path.push(index);
+                }
+                subtrees[index].insert_node(point, path)
+            }
+            // If we get here, the point was not inserted, which should not happen
+            None => bail!("Point not inserted into any subtree"),
+        }
+    }
+
+    /// Move this node's points into its subtrees, leaving it a pure routing node
+    fn push_points_down(&mut self) -> Result<()> {
+        let points = std::mem::take(&mut self.points);
+        let subtrees = self
+            .subtrees
+            .as_mut()
+            .ok_or_eyre("subtrees not created, this is a bug")?;
+        for point in points {
+            let index = Self::child_index(subtrees, point.point())
+                .ok_or_eyre("stored point not inside any subtree, this is a bug")?;
+            subtrees[index].insert_node(point, None)?;
+        }
+        Ok(())
+    }
+
+    /// Change the maximum number of points per node for every node, going forward.
+    /// Nodes already holding more points than the new maximum subdivide on their next insert,
+    /// while a larger maximum lets nodes accept more points before subdividing.
+    /// Existing subtrees are never merged back, even if they end up under-full.
+    pub fn set_max_points(&mut self, max_points: NonZero<usize>) {
+        self.max_points = max_points;
+        for subtree in self.subtrees.iter_mut().flatten() {
+            subtree.set_max_points(max_points);
+        }
+    }
+
+    /// Reserve capacity for at least `additional` more points in this node's own points Vec.
+    /// Only this node is affected, not its subtrees, and it doesn't change when the node
+    /// subdivides, which is still decided by `max_points`.
+    pub fn reserve(&mut self, additional: usize) {
+        self.points.reserve(additional);
+    }
+
+    fn at_max_depth(&self) -> bool {
+        self.max_depth
+            .is_some_and(|max_depth| self.depth >= max_depth.get())
+    }
+
+    /// The index of the subtree a point belongs in. Points on an internal boundary belong to
+    /// exactly one subtree by half-open containment, points on the outer upper edge go to the
+    /// subtree sharing that edge.
+    fn child_index(subtrees: &[QuadTree<N, V, S, F>], point: &Point<N, F>) -> Option<usize> {
+        subtrees
+            .iter()
+            .position(|subtree| subtree.region.contains(point))
+            .or_else(|| {
+                subtrees
+                    .iter()
+                    .position(|subtree| subtree.region.contains_inclusive(point))
+            })
+    }
+
+    /// Whether an item with exactly this point is stored in the [QuadTree].
+    /// Only descends into the single subtree whose region contains the point.
+    pub fn contains_point(&self, point: &Point<N, F>) -> bool {
+        if !self.region.contains_inclusive(point) {
+            return false;
+        }
+        self.points.iter().any(|item| item.point() == point)
+            || self.subtrees.as_ref().is_some_and(|subtrees| {
+                Self::child_index(subtrees, point)
+                    .is_some_and(|index| subtrees[index].contains_point(point))
+            })
+    }
+
+    /// Drop every stored item for which `f` returns false. Each item is seen exactly once.
+    /// Leaf subtrees left holding few enough items to fit in their parent are collapsed into it.
+    pub fn retain<P: FnMut(&V) -> bool>(&mut self, mut f: P) {
+        self.retain_with(&mut f);
+    }
+
+    fn retain_with<P: FnMut(&V) -> bool>(&mut self, f: &mut P) {
+        self.points.retain(|item| f(item));
+        if let Some(subtrees) = &mut self.subtrees {
+            for subtree in subtrees.iter_mut() {
+                subtree.retain_with(f);
+            }
+            let remaining = self.points.len()
+                + subtrees
+                    .iter()
+                    .map(|subtree| subtree.points.len())
+                    .sum::<usize>();
+            if subtrees.iter().all(|subtree| subtree.subtrees.is_none())
+                && remaining <= self.max_points.get()
+            {
+                for subtree in subtrees.iter_mut() {
+                    self.points.append(&mut subtree.points);
+                }
+                self.subtrees = None;
+            }
+        }
+    }
+
+    /// Try to insert every item, collecting each outcome in order.
+    /// A failing item doesn't stop the remaining items from being inserted.
+    pub fn insert_many(&mut self, items: impl IntoIterator<Item = V>) -> Vec<Result<()>> {
+        items.into_iter().map(|item| self.insert(item)).collect()
+    }
+
+    /// Insert every item, stopping at the first that fails.
+    /// Items before the failing one remain inserted.
+    pub fn try_extend(&mut self, items: impl IntoIterator<Item = V>) -> Result<()> {
+        items.into_iter().try_for_each(|item| self.insert(item))
+    }
+
+    /// Snap `point` to the nearest multiple of `cell` on every axis, collapsing nearby points
+    /// onto one grid point, e.g. to downsample a noisy point cloud.
+    /// Items can't be moved generically, so the caller builds them: if an item is already
+    /// stored at the snapped point `merge` updates it (say, incrementing a count), otherwise
+    /// the item made by `create` from the snapped point is inserted.
+    /// Errors if `cell` isn't positive and finite, or the snapped point can't be inserted.
+    pub fn insert_quantized(
+        &mut self,
+        point: &Point<N, F>,
+        cell: F,
+        create: impl FnOnce(Point<N, F>) -> V,
+        merge: impl FnOnce(&mut V),
+    ) -> Result<()> {
+        ensure!(
+            cell.is_finite() && cell > F::zero(),
+            "Cell size must be positive and finite, got {}",
+            cell
+        );
+        let snapped = Point::from(point.to_array().map(|value| (value / cell).round() * cell));
+        let probe = PointQuery::try_new(&snapped)?;
+        if let Some(existing) = self.query_mut(&probe).next() {
+            merge(existing);
+            return Ok(());
+        }
+        self.insert(create(snapped))
+    }
+
+    /// Insert a point, growing the [QuadTree] if the point is outside its region.
+    /// The tree is re-rooted by doubling the region towards the point, with the old tree
+    /// becoming one of the new root's subtrees, until the point is covered.
+    /// Each re-root doubles the extent, so reaching a point at distance `d` costs
+    /// O(log(d / size)) re-roots, and inserts within the region cost the same as [QuadTree::insert].
+    /// With a maximum depth, an old tree that is already as deep as allowed has its items
+    /// re-inserted under the new root instead, so growing never exceeds the maximum depth.
+    pub fn insert_growing(&mut self, point: V) -> Result<()> {
+        self.grow_to_fit(point.point())?;
+        self.insert_node(point, None)
+    }
+
+    fn grow_to_fit(&mut self, point: &Point<N, F>) -> Result<()> {
+        ensure!(
+            point
+                .dimension_values()
+                .iter()
+                .all(|value| value.is_finite()),
+            "Point must be finite to grow the region"
+        );
+        while !self.region.contains_inclusive(point) {
+            self.grow_towards(point)?;
+        }
+        Ok(())
+    }
+
+    fn grow_towards(&mut self, point: &Point<N, F>) -> Result<()> {
+        let intervals = self
+            .region
+            .intervals()
+            .iter()
+            .zip(point.dimension_values())
+            .map(|(interval, value)| {
+                let length = interval.length();
+                if value < interval.start() {
+                    Interval::try_new(*interval.start() - length, *interval.end())
+                } else {
+                    Interval::try_new(*interval.start(), *interval.end() + length)
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let root = QuadTree {
+            region: Region::try_new(&intervals)?,
+            subtrees: None,
+            points: Vec::with_capacity(self.max_points.get()),
+            max_points: self.max_points,
+            max_depth: self.max_depth,
+            depth: self.depth,
+            auto_grow: self.auto_grow,
+            leaves_only: self.leaves_only,
+            strategy: PhantomData,
+        };
+
+        let mut old = std::mem::replace(self, root);
+        // Nesting the old tree would push its deepest nodes past the maximum depth,
+        // so re-insert its items under the new root instead
+        if self
+            .max_depth
+            .is_some_and(|max_depth| old.depth() >= max_depth.get())
+        {
+            for item in old.into_items() {
+                self.insert_node(item, None)?;
+            }
+            return Ok(());
+        }
+        old.increment_depth();
+        self.subdivide();
+        let subtrees = self
+            .subtrees
+            .as_mut()
+            .ok_or_eyre("subtrees not created, this is a bug")?;
+        match subtrees
+            .iter_mut()
+            .find(|subtree| subtree.region == old.region)
         {
-            if subtree.region.contains(point.point()) {
-                return subtree.insert(point);
+            Some(subtree) => *subtree = old,
+            // Rounding left the old region misaligned with the new subtrees, so re-insert instead
+            None => {
+                for item in old.into_items() {
+                    self.insert_node(item, None)?;
+                }
             }
         }
+        Ok(())
+    }
+
+    fn increment_depth(&mut self) {
+        self.depth += 1;
+        for subtree in self.subtrees.iter_mut().flatten() {
+            subtree.increment_depth();
+        }
+    }
+
+    /// Consume the tree, returning every stored item
+    fn into_items(self) -> Vec<V> {
+        let mut items = self.points;
+        for subtree in self.subtrees.into_iter().flatten() {
+            items.extend(subtree.into_items());
+        }
+        items
+    }
 
-        // If we get here, the point was not inserted, which should not happen
-        bail!("Point not inserted into any subtree");
+    /// Whether subdividing would produce any child smaller than this node
+    fn can_subdivide(&self) -> bool {
+        S::subdivide(&self.region)
+            .iter()
+            .any(|region| *region != self.region)
     }
 
     fn subdivide(&mut self) {
-        let subregions = self.region.subdivide();
         self.subtrees = Some(
-            subregions
+            S::subdivide(&self.region)
                 .into_iter()
-                .map(|region| {
-                    QuadTree::new(
-                        &Region::new(&region),
-                        NonZero::new(self.points.capacity()).expect("non-zero capacity"),
-                    )
+                .map(|region| QuadTree {
+                    region,
+                    subtrees: None,
+                    points: Vec::with_capacity(self.max_points.get()),
+                    max_points: self.max_points,
+                    max_depth: self.max_depth,
+                    depth: self.depth + 1,
+                    auto_grow: false,
+                    leaves_only: self.leaves_only,
+                    strategy: PhantomData,
                 })
                 .collect(),
         );
     }
 
-    /// Query the [QuadTree] with a region (any type that implements the [Query] trait).
-    pub fn query<'a, Q>(&'a self, query: &'a Q) -> Box<dyn Iterator<Item = &'a V> + 'a>
-    where
-        Q: Query<N> + 'a,
-    {
-        let my_iter = self
-            .points
-            .iter()
-            .filter_map(move |point| query.contains(point.point()).then_some(point.item()));
+    /// Returns a [QuadTree] with the same region and subtree structure but no stored points,
+    /// ready to be refilled with data following a similar distribution.
+    /// Inserts into the copy may still trigger further subdivision.
+    pub fn clone_structure_empty(&self) -> QuadTree<N, V, S, F> {
+        QuadTree {
+            region: self.region.clone(),
+            subtrees: self.subtrees.as_ref().map(|subtrees| {
+                subtrees
+                    .iter()
+                    .map(|subtree| subtree.clone_structure_empty())
+                    .collect()
+            }),
+            points: if self.leaves_only && self.subtrees.is_some() {
+                Vec::new()
+            } else {
+                Vec::with_capacity(self.max_points.get())
+            },
+            max_points: self.max_points,
+            max_depth: self.max_depth,
+            depth: self.depth,
+            auto_grow: self.auto_grow,
+            leaves_only: self.leaves_only,
+            strategy: PhantomData,
+        }
+    }
+
+    /// Query the [QuadTree] with a region (any type that implements the [Query] trait).
+    /// Nodes entirely inside the query yield all their points without calling `contains`,
+    /// and nodes entirely outside it are skipped, see [Query::region_relationship].
+    /// The iterator is lazy, the tree is only walked as far as the matches taken.
+    pub fn query<'a, Q>(&'a self, query: &'a Q) -> Box<dyn Iterator<Item = &'a V> + 'a>
+    where
+        Q: Query<N, F> + 'a,
+    {
+        match query.region_relationship(&self.region) {
+            Containment::Outside => Box::new(std::iter::empty()),
+            Containment::Inside => self.items(),
+            Containment::Partial => {
+                let my_iter = self
+                    .points
+                    .iter()
+                    .filter_map(move |point| query.contains(point.point()).then_some(point.item()));
+
+                let subtree_iter = self
+                    .subtrees
+                    .iter()
+                    .flat_map(|subtrees| subtrees.iter().flat_map(|subtree| subtree.query(query)));
+
+                Box::new(my_iter.chain(subtree_iter))
+            }
+        }
+    }
+
+    /// Query the [QuadTree] like [QuadTree::query], eagerly pushing every match into a
+    /// caller-provided buffer. This is a plain recursive walk, so no iterators are boxed per node,
+    /// and the buffer can be reused between queries.
+    pub fn query_into<'a, Q: Query<N, F>>(&'a self, query: &Q, out: &mut Vec<&'a V>) {
+        self.visit_matches(query, &mut |stored| out.push(stored.item()));
+    }
+
+    /// Query the [QuadTree] with a query taken by value, collecting the matches.
+    /// The query isn't borrowed for the lifetime of the results, so temporaries can be passed inline.
+    pub fn query_owned<Q: Query<N, F>>(&self, query: Q) -> Vec<&V> {
+        let mut results = Vec::new();
+        self.query_into(&query, &mut results);
+        results
+    }
+
+    /// Query the half-open box from `min` to `max`, without building the [Region] by hand.
+    /// Errors if `min` isn't below `max` on every axis, see [Interval::try_new].
+    pub fn query_range(&self, min: &[F; N], max: &[F; N]) -> Result<Vec<&V>> {
+        let intervals = min
+            .iter()
+            .zip(max)
+            .map(|(&min, &max)| Interval::try_new(min, max))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self.query_owned(Region::try_new(&intervals)?))
+    }
+
+    /// Query the [QuadTree], cloning each match so the results don't borrow the tree
+    pub fn query_cloned<Q: Query<N, F>>(&self, query: &Q) -> Vec<V>
+    where
+        V: Clone,
+    {
+        let mut results = Vec::new();
+        self.visit_matches(query, &mut |stored| results.push(stored.item().clone()));
+        results
+    }
+
+    /// Query the [QuadTree], returning the matches sorted by their [Point].
+    /// Unlike [QuadTree::query], the order doesn't depend on subdivision or insertion history.
+    /// Matches sharing a point keep their traversal order.
+    pub fn query_sorted<Q: Query<N, F>>(&self, query: &Q) -> Vec<&V> {
+        let mut results = Vec::new();
+        self.visit_matches(query, &mut |stored| results.push(stored));
+        results.sort_by_key(|stored| *stored.point());
+        results.into_iter().map(|stored| stored.item()).collect()
+    }
+
+    /// The items within `radius` of `center` (closed, like [DistanceQuery]), each paired with
+    /// its distance to `center`, nearest first.
+    /// Panics if `radius` is negative or not finite, as [DistanceQuery::new] does.
+    pub fn query_by_distance(&self, center: &Point<N, F>, radius: F) -> Vec<(&V, F)> {
+        let mut results = Vec::new();
+        self.visit_matches(&DistanceQuery::new(center, radius), &mut |stored| {
+            results.push((stored.item(), stored.point().distance(center)))
+        });
+        results.sort_by_key(|(_, distance)| OrderedFloat(*distance));
+        results
+    }
+
+    /// Query the [QuadTree], yielding each match's stored point alongside the item
+    pub fn query_with_points<'a, Q: Query<N, F>>(
+        &'a self,
+        query: &Q,
+    ) -> impl Iterator<Item = (&'a Point<N, F>, &'a V)> + 'a {
+        let mut results = Vec::new();
+        self.visit_matches(query, &mut |stored| {
+            results.push((stored.point(), stored.item()))
+        });
+        results.into_iter()
+    }
+
+    /// Call `f` on every item matching the query as it is found, pruning like [QuadTree::query].
+    /// Nothing is collected or boxed, so this is the cheapest way to consume a large result set.
+    pub fn for_each_match<Q: Query<N, F>, C: FnMut(&V)>(&self, query: &Q, mut f: C) {
+        self.visit_matches(query, &mut |stored| f(stored.item()));
+    }
+
+    /// Call `f` on every stored element matching the query, pruning like [QuadTree::query]
+    fn visit_matches<'a, Q, C>(&'a self, query: &Q, f: &mut C)
+    where
+        Q: Query<N, F>,
+        C: FnMut(&'a V),
+    {
+        match query.region_relationship(&self.region) {
+            Containment::Outside => {}
+            Containment::Inside => self.visit_all(f),
+            Containment::Partial => {
+                for stored in &self.points {
+                    if query.contains(stored.point()) {
+                        f(stored);
+                    }
+                }
+                for subtree in self.subtrees.iter().flatten() {
+                    subtree.visit_matches(query, f);
+                }
+            }
+        }
+    }
+
+    /// Call `f` on every element stored in this node and its subtrees
+    fn visit_all<'a, C: FnMut(&'a V)>(&'a self, f: &mut C) {
+        self.points.iter().for_each(&mut *f);
+        for subtree in self.subtrees.iter().flatten() {
+            subtree.visit_all(f);
+        }
+    }
+
+    /// Count the matches of a query without collecting them, using the same pruning as
+    /// [QuadTree::query]. Equal to `query(q).count()`.
+    pub fn count<Q: Query<N, F>>(&self, query: &Q) -> usize {
+        match query.region_relationship(&self.region) {
+            Containment::Outside => 0,
+            Containment::Inside => self.len(),
+            Containment::Partial => {
+                self.points
+                    .iter()
+                    .filter(|point| query.contains(point.point()))
+                    .count()
+                    + self
+                        .subtrees
+                        .iter()
+                        .flatten()
+                        .map(|subtree| subtree.count(query))
+                        .sum::<usize>()
+            }
+        }
+    }
+
+    /// The component-wise mean of the points matching a query, None if nothing matches
+    pub fn centroid<Q: Query<N, F>>(&self, query: &Q) -> Option<Point<N, F>> {
+        let mut sums = [F::zero(); N];
+        let mut matches = 0;
+        self.visit_matches(query, &mut |stored| {
+            for (sum, value) in sums.iter_mut().zip(stored.point().dimension_values()) {
+                *sum = *sum + *value;
+            }
+            matches += 1;
+        });
+        (matches > 0).then(|| Point::from(sums.map(|sum| sum / F::from_f64(matches as f64))))
+    }
+
+    /// Number of items stored in this node and its subtrees
+    fn len(&self) -> usize {
+        self.points.len()
+            + self
+                .subtrees
+                .iter()
+                .flatten()
+                .map(|subtree| subtree.len())
+                .sum::<usize>()
+    }
+
+    /// Query the [QuadTree] and count the matches per cell of a grid laid over the query's
+    /// bounding region, with `parts_per_axis` cells along each axis.
+    /// Returns a flat Vec of `parts_per_axis^N` counts where dimension 0 varies fastest,
+    /// i.e. the cell `[i, j]` of a 2D grid is at index `i + j * parts_per_axis`.
+    /// Errors if the grid has too many cells to count or allocate.
+    pub fn query_heatmap<Q: Query<N, F>>(
+        &self,
+        query: &Q,
+        parts_per_axis: NonZero<usize>,
+    ) -> Result<Vec<usize>> {
+        let parts = parts_per_axis.get();
+        let cells = parts.checked_pow(N as u32);
+        ensure!(
+            cells.is_some(),
+            "a heatmap of {} parts per axis in {} dimensions has too many cells",
+            parts,
+            N
+        );
+        let cells = cells.expect("checked above");
+        let mut counts = Vec::new();
+        counts.try_reserve_exact(cells)?;
+        counts.resize(cells, 0);
+
+        let mut results = Vec::new();
+        self.query_into(query, &mut results);
+        for item in results {
+            let index = query
+                .region()
+                .intervals()
+                .iter()
+                .zip(item.point().dimension_values())
+                .rev()
+                .fold(0, |index, (interval, value)| {
+                    let fraction: f64 = ((*value - *interval.start()) / interval.length()).into();
+                    let cell = ((fraction * parts as f64) as usize).min(parts - 1);
+                    index * parts + cell
+                });
+            counts[index] += 1;
+        }
+        Ok(counts)
+    }
+
+    /// Query the [QuadTree] like [QuadTree::query], but yield mutable references to the stored items.
+    /// Only the non-spatial parts of an item should be changed: moving its point would leave it
+    /// in the wrong node. The tree stays mutably borrowed for as long as the iterator lives.
+    pub fn query_mut<'a, Q>(&'a mut self, query: &'a Q) -> Box<dyn Iterator<Item = &'a mut V> + 'a>
+    where
+        Q: Query<N, F> + 'a,
+    {
+        match query.region_relationship(&self.region) {
+            Containment::Outside => Box::new(std::iter::empty()),
+            Containment::Inside => self.items_mut(),
+            Containment::Partial => {
+                let my_iter = self
+                    .points
+                    .iter_mut()
+                    .filter(move |point| query.contains(point.point()));
+
+                let subtree_iter = self.subtrees.iter_mut().flat_map(move |subtrees| {
+                    subtrees
+                        .iter_mut()
+                        .flat_map(move |subtree| subtree.query_mut(query))
+                });
+
+                Box::new(my_iter.chain(subtree_iter))
+            }
+        }
+    }
+
+    /// Query the [QuadTree] like [QuadTree::query], searching independent subtrees in parallel.
+    /// Returns the same items as `query`, though not necessarily in the same order.
+    #[cfg(feature = "rayon")]
+    pub fn par_query<Q>(&self, query: &Q) -> Vec<&V>
+    where
+        Q: Query<N, F> + Sync,
+        V: Sync,
+    {
+        match query.region_relationship(&self.region) {
+            Containment::Outside => Vec::new(),
+            Containment::Inside => {
+                let mut results = Vec::new();
+                self.items_into(&mut results);
+                results
+            }
+            Containment::Partial => {
+                let mut results: Vec<&V> = self
+                    .points
+                    .iter()
+                    .filter_map(|point| query.contains(point.point()).then_some(point.item()))
+                    .collect();
+                if let Some(subtrees) = &self.subtrees {
+                    results.par_extend(
+                        subtrees
+                            .par_iter()
+                            .flat_map_iter(|subtree| subtree.par_query(query)),
+                    );
+                }
+                results
+            }
+        }
+    }
+
+    /// Push all items stored in this node and its subtrees
+    #[cfg(feature = "rayon")]
+    fn items_into<'a>(&'a self, out: &mut Vec<&'a V>) {
+        self.visit_all(&mut |stored| out.push(stored.item()));
+    }
+
+    /// All items stored in this node and its subtrees
+    fn items(&self) -> Box<dyn Iterator<Item = &V> + '_> {
+        let my_iter = self.points.iter().map(|point| point.item());
+        let subtree_iter = self
+            .subtrees
+            .iter()
+            .flat_map(|subtrees| subtrees.iter().flat_map(|subtree| subtree.items()));
+        Box::new(my_iter.chain(subtree_iter))
+    }
+
+    fn items_mut(&mut self) -> Box<dyn Iterator<Item = &mut V> + '_> {
+        let my_iter = self.points.iter_mut();
+        let subtree_iter = self
+            .subtrees
+            .iter_mut()
+            .flat_map(|subtrees| subtrees.iter_mut().flat_map(|subtree| subtree.items_mut()));
+        Box::new(my_iter.chain(subtree_iter))
+    }
+
+    /// Query the [QuadTree] and split the matches in one pass into those whose point lies
+    /// inside `partition` and those that do not.
+    pub fn query_partition<'a, Q>(
+        &'a self,
+        query: &'a Q,
+        partition: &Region<N, F>,
+    ) -> (Vec<&'a V>, Vec<&'a V>)
+    where
+        Q: Query<N, F> + 'a,
+    {
+        self.query(query)
+            .partition(|item| partition.contains(item.point()))
+    }
+
+    /// Height of the tree, a leaf being depth 1
+    pub fn depth(&self) -> usize {
+        1 + self
+            .subtrees
+            .iter()
+            .flatten()
+            .map(|subtree| subtree.depth())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Total number of nodes in the tree, including this one
+    pub fn node_count(&self) -> usize {
+        1 + self
+            .subtrees
+            .iter()
+            .flatten()
+            .map(|subtree| subtree.node_count())
+            .sum::<usize>()
+    }
+
+    /// The stored item closest to `point`, or None if the tree is empty.
+    /// Subtrees are visited nearest first and skipped once they can't beat the best so far.
+    /// Ties return any one of the closest items.
+    pub fn nearest_one(&self, point: &Point<N, F>) -> Option<&V> {
+        let mut best = None;
+        self.nearest_one_into(point, &mut best);
+        best.map(|(item, _)| item)
+    }
+
+    fn nearest_one_into<'a>(&'a self, point: &Point<N, F>, best: &mut Option<(&'a V, F)>) {
+        if let Some((_, best_distance)) = best
+            && self.region.min_distance(point) > *best_distance
+        {
+            return;
+        }
+
+        for item in &self.points {
+            let distance = item.point().distance(point);
+            if best.is_none_or(|(_, best_distance)| distance < best_distance) {
+                *best = Some((item.item(), distance));
+            }
+        }
+
+        if let Some(subtrees) = &self.subtrees {
+            let mut ordered: Vec<_> = subtrees
+                .iter()
+                .map(|subtree| (subtree.region.min_distance(point), subtree))
+                .collect();
+            ordered.sort_by_key(|(distance, _)| OrderedFloat(*distance));
+            for (_, subtree) in ordered {
+                subtree.nearest_one_into(point, best);
+            }
+        }
+    }
+
+    /// The `k` stored items closest to `point`, nearest first
+    pub fn nearest(&self, point: &Point<N, F>, k: usize) -> Vec<&V> {
+        self.nearest_iter(point).take(k).collect()
+    }
+
+    /// Lazily yield stored items in order of increasing distance from `point`.
+    /// Nodes are expanded best-first, so only as much of the tree is visited as is consumed.
+    pub fn nearest_iter<'a>(&'a self, point: &Point<N, F>) -> impl Iterator<Item = &'a V> + 'a {
+        let point = *point;
+        let mut frontier = BinaryHeap::from([Candidate {
+            distance: OrderedFloat(self.region.min_distance(&point)),
+            entry: Frontier::Node(self),
+        }]);
+        std::iter::from_fn(move || {
+            while let Some(Candidate { entry, .. }) = frontier.pop() {
+                match entry {
+                    Frontier::Item(item) => return Some(item),
+                    Frontier::Node(node) => {
+                        frontier.extend(node.points.iter().map(|stored| Candidate {
+                            distance: OrderedFloat(stored.point().distance(&point)),
+                            entry: Frontier::Item(stored.item()),
+                        }));
+                        frontier.extend(node.subtrees.iter().flatten().map(|subtree| Candidate {
+                            distance: OrderedFloat(subtree.region.min_distance(&point)),
+                            entry: Frontier::Node(subtree),
+                        }));
+                    }
+                }
+            }
+            None
+        })
+    }
+
+    /// Count the leaf nodes whose region intersects the query's bounding region.
+    /// Cheaper than collecting results when only the spread of a query is needed.
+    pub fn leaf_cells_touched<Q: Query<N, F>>(&self, query: &Q) -> usize {
+        if !self.region.intersects(query.region()) {
+            return 0;
+        }
+        match &self.subtrees {
+            Some(subtrees) => subtrees
+                .iter()
+                .map(|subtree| subtree.leaf_cells_touched(query))
+                .sum(),
+            None => 1,
+        }
+    }
+
+    /// Returns all leaf nodes (nodes without subtrees)
+    pub fn leaves(&self) -> Vec<&QuadTree<N, V, S, F>> {
+        match &self.subtrees {
+            Some(subtrees) => subtrees
+                .iter()
+                .flat_map(|subtree| subtree.leaves())
+                .collect(),
+            None => vec![self],
+        }
+    }
+
+    /// Estimated heap bytes used by the tree: each node's points allocation plus its subtrees
+    /// allocation, summed recursively. Heap memory owned by the items themselves is not counted.
+    pub fn memory_footprint(&self) -> usize {
+        let points = self.points.capacity() * size_of::<V>();
+        let subtrees = self.subtrees.as_ref().map_or(0, |subtrees| {
+            subtrees.capacity() * size_of::<QuadTree<N, V, S, F>>()
+                + subtrees
+                    .iter()
+                    .map(|subtree| subtree.memory_footprint())
+                    .sum::<usize>()
+        });
+        points + subtrees
+    }
+
+    /// Each leaf's region paired with the number of points it holds, e.g. for heatmaps.
+    /// Points kept by internal nodes, see [QuadTreeBuilder::leaves_only], are not counted.
+    pub fn leaf_densities(&self) -> Vec<(Region<N, F>, usize)> {
+        self.leaves()
+            .into_iter()
+            .map(|leaf| (leaf.region.clone(), leaf.points.len()))
+            .collect()
+    }
+
+    /// Whether this node stores its points directly, without subtrees
+    pub fn is_leaf(&self) -> bool {
+        self.subtrees.is_none()
+    }
+
+    /// The subtrees of this node, None for a leaf
+    pub fn children(&self) -> Option<&[QuadTree<N, V, S, F>]> {
+        self.subtrees.as_deref()
+    }
+
+    /// Walk the tree and re-insert any item whose point is not inside the region of the node
+    /// holding it. Returns the number of items moved.
+    /// Items outside the root region cannot be re-homed and are left in place.
+    pub fn repair(&mut self) -> usize {
+        let root = self.region.clone();
+        let mut misplaced = Vec::new();
+        self.drain_misplaced(&root, &mut misplaced);
+
+        let moved = misplaced.len();
+        for item in misplaced {
+            self.insert(item)
+                .expect("misplaced item is inside the root region");
+        }
+        moved
+    }
+
+    fn drain_misplaced(&mut self, root: &Region<N, F>, out: &mut Vec<V>) {
+        let region = &self.region;
+        out.extend(self.points.extract_if(.., |item| {
+            !region.contains_inclusive(item.point()) && root.contains_inclusive(item.point())
+        }));
+        for subtree in self.subtrees.iter_mut().flatten() {
+            subtree.drain_misplaced(root, out);
+        }
+    }
+
+    /// Returns all regions
+    #[cfg(feature = "nannou")]
+    pub fn regions(&self) -> Vec<Region<N, F>> {
+        let mut regions = vec![self.region.clone()];
+
+        regions.extend(
+            self.subtrees
+                .iter()
+                .flat_map(|subtrees| subtrees.iter().flat_map(|subtree| subtree.regions())),
+        );
+
+        regions
+    }
+}
+
+/// A node or item waiting to be visited by [QuadTree::nearest_iter]
+enum Frontier<'a, const N: usize, V, S, F> {
+    Node(&'a QuadTree<N, V, S, F>),
+    Item(&'a V),
+}
+
+/// A [Frontier] entry keyed by its (lower bound) distance, ordered so that
+/// [BinaryHeap] pops the closest first
+struct Candidate<'a, const N: usize, V, S, F> {
+    distance: OrderedFloat<F>,
+    entry: Frontier<'a, N, V, S, F>,
+}
+
+impl<const N: usize, V, S, F: Scalar> PartialEq for Candidate<'_, N, V, S, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<const N: usize, V, S, F: Scalar> Eq for Candidate<'_, N, V, S, F> {}
+
+impl<const N: usize, V, S, F: Scalar> PartialOrd for Candidate<'_, N, V, S, F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize, V, S, F: Scalar> Ord for Candidate<'_, N, V, S, F> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed for a min-heap; on ties prefer items so they're yielded before expanding nodes
+        other.distance.cmp(&self.distance).then_with(|| {
+            matches!(self.entry, Frontier::Item(_)).cmp(&matches!(other.entry, Frontier::Item(_)))
+        })
+    }
+}
+
+/// Builder for a [QuadTree] with more configuration than [QuadTree::new].
+/// ```rust
+/// # fn main() -> eyre::Result<()> {
+/// # use quadtree::{interval::Interval, point::Point, quadtree::QuadTreeBuilder, region::Region};
+/// # use std::num::NonZero;
+/// let region = Region::new(&[Interval::try_new(0.0, 10.0)?, Interval::try_new(0.0, 10.0)?]);
+/// let mut quadtree = QuadTreeBuilder::new()
+///     .region(region)
+///     .max_points(NonZero::new(4).expect("4 is non-zero"))
+///     .max_depth(NonZero::new(8).expect("8 is non-zero"))
+///     .auto_grow(true)
+///     .build()?;
+///
+/// // Outside the initial region, but the tree grows to fit it
+/// quadtree.insert(Point::new(&[50, 50]))?;
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct QuadTreeBuilder<const N: usize, V, S = SplitAllAxes, F = f64> {
+    region: Option<Region<N, F>>,
+    max_points: NonZero<usize>,
+    max_depth: Option<NonZero<usize>>,
+    auto_grow: bool,
+    leaves_only: bool,
+    item: PhantomData<V>,
+    strategy: PhantomData<fn() -> S>,
+}
+
+impl<const N: usize, F: Scalar, V: Storable<V, N, F>> Default
+    for QuadTreeBuilder<N, V, SplitAllAxes, F>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, F: Scalar, V: Storable<V, N, F>> QuadTreeBuilder<N, V, SplitAllAxes, F> {
+    /// A builder with [DEFAULT_MAX_POINTS], no maximum depth and no auto growing.
+    /// A region must be set before building.
+    pub fn new() -> Self {
+        QuadTreeBuilder {
+            region: None,
+            max_points: DEFAULT_MAX_POINTS,
+            max_depth: None,
+            auto_grow: false,
+            leaves_only: true,
+            item: PhantomData,
+            strategy: PhantomData,
+        }
+    }
+}
+
+impl<const N: usize, F: Scalar, V: Storable<V, N, F>, S: SubdivisionStrategy<N, F>>
+    QuadTreeBuilder<N, V, S, F>
+{
+    /// How nodes split when they overflow, see [SubdivisionStrategy]
+    pub fn strategy<T: SubdivisionStrategy<N, F>>(self) -> QuadTreeBuilder<N, V, T, F> {
+        QuadTreeBuilder {
+            region: self.region,
+            max_points: self.max_points,
+            max_depth: self.max_depth,
+            auto_grow: self.auto_grow,
+            leaves_only: self.leaves_only,
+            item: PhantomData,
+            strategy: PhantomData,
+        }
+    }
+
+    /// The bounds of the tree
+    pub fn region(mut self, region: Region<N, F>) -> Self {
+        self.region = Some(region);
+        self
+    }
+
+    /// The maximum number of points each node stores before subdividing
+    pub fn max_points(mut self, max_points: NonZero<usize>) -> Self {
+        self.max_points = max_points;
+        self
+    }
+
+    /// Cap subdivision at this depth, see [QuadTree::with_max_depth]
+    pub fn max_depth(mut self, max_depth: NonZero<usize>) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Grow the tree on insert of a point outside its region, see [QuadTree::insert_growing]
+    pub fn auto_grow(mut self, auto_grow: bool) -> Self {
+        self.auto_grow = auto_grow;
+        self
+    }
+
+    /// Move a node's points into its subtrees when it subdivides, so only leaves hold points
+    /// and internal nodes are pure routing nodes that queries pass straight through.
+    /// On by default. When off, a subdivided node keeps the points it held before subdividing.
+    pub fn leaves_only(mut self, leaves_only: bool) -> Self {
+        self.leaves_only = leaves_only;
+        self
+    }
+
+    pub fn build(self) -> Result<QuadTree<N, V, S, F>> {
+        let region = self
+            .region
+            .ok_or_eyre("a region is required to build a QuadTree")?;
+        Ok(QuadTree {
+            max_depth: self.max_depth,
+            auto_grow: self.auto_grow,
+            leaves_only: self.leaves_only,
+            ..QuadTree::empty(&region, self.max_points)
+        })
+    }
+}
+
+/// An empty [QuadTree] over the unit cube, `[0, 1]` on every axis, with [DEFAULT_MAX_POINTS]
+/// per node. As with any tree, points on the upper edge (a coordinate of exactly 1) are accepted.
+/// Use [QuadTree::new] or [QuadTreeBuilder] for any other bounds.
+impl<const N: usize, F: Scalar, V: Storable<V, N, F>, S: SubdivisionStrategy<N, F>> Default
+    for QuadTree<N, V, S, F>
+{
+    fn default() -> Self {
+        let unit = Interval::try_new(F::zero(), F::one()).expect("0 is less than 1");
+        QuadTree::empty(&Region::new(&[unit; N]), DEFAULT_MAX_POINTS)
+    }
+}
+
+/// Build a [QuadTree] over the tightest region enclosing the items (see [Region::bounding]),
+/// with [DEFAULT_MAX_POINTS] per node.
+/// Panics if the iterator is empty, as there is no region to build the tree over.
+impl<const N: usize, F: Scalar, V: Storable<V, N, F>, S: SubdivisionStrategy<N, F>> FromIterator<V>
+    for QuadTree<N, V, S, F>
+{
+    fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
+        let items: Vec<V> = iter.into_iter().collect();
+        let points: Vec<Point<N, F>> = items.iter().map(|item| *item.point()).collect();
+        let region = Region::bounding(&points).expect("cannot collect a QuadTree from no items");
+
+        let mut quadtree = QuadTree::empty(&region, DEFAULT_MAX_POINTS);
+        for item in items {
+            quadtree
+                .insert(item)
+                .expect("bounding region contains every item");
+        }
+        quadtree
+    }
+}
+
+/// Insert every item, see [QuadTree::try_extend] for a fallible version.
+/// Panics if an item can't be inserted, e.g. it is outside the region of a tree without auto_grow.
+impl<const N: usize, F: Scalar, V: Storable<V, N, F>, S: SubdivisionStrategy<N, F>> Extend<V>
+    for QuadTree<N, V, S, F>
+{
+    fn extend<I: IntoIterator<Item = V>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item).expect("failed to extend QuadTree");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    #[derive(Clone)]
+    pub struct TestStruct(Point<2>, String);
+    impl Storable<TestStruct, 2> for TestStruct {
+        fn point(&self) -> &Point<2> {
+            &self.0
+        }
+
+        fn item(&self) -> &Self {
+            self
+        }
+    }
+
+    #[test]
+    fn test_quadtree_insert_outside_region() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(4).unwrap());
+
+        let point_outside = TestStruct(Point::new(&[11, 5]), "data".to_string());
+        assert!(quadtree.insert(point_outside).is_err());
+    }
+
+    #[test]
+    fn test_quadtree_insert_on_upper_edge() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        for i in 0..4 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i, i]), "data".to_string()))
+                .unwrap();
+        }
+        assert!(quadtree.subtrees.is_some());
+
+        // The maximum corner and a point on the upper x edge, both past the root's capacity
+        let corner = Point::new(&[10, 10]);
+        let edge = Point::new(&[10, 2]);
+        quadtree
+            .insert(TestStruct(corner, "corner".to_string()))
+            .unwrap();
+        quadtree
+            .insert(TestStruct(edge, "edge".to_string()))
+            .unwrap();
+        assert!(quadtree.contains_point(&corner));
+        assert!(quadtree.contains_point(&edge));
+        assert_eq!(quadtree.repair(), 0);
+
+        // Half-open region queries still exclude the upper edge
+        assert_eq!(quadtree.query(&region).count(), 4);
+        let everything = crate::query::InclusiveRegion::new(&region);
+        assert_eq!(quadtree.query(&everything).count(), 6);
+
+        // Just beyond the edge is still rejected
+        assert!(
+            quadtree
+                .insert(TestStruct(Point::new(&[10.1, 10.0]), "outside".to_string()))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_quadtree_initialise() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let _: QuadTree<2, TestStruct> = QuadTree::new(&region, NonZero::new(4).unwrap());
+    }
+
+    #[test]
+    fn test_quadtree_insert_below_capacity() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(4).unwrap());
+
+        for i in 0..4 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i, 0]), "data".to_string()))
+                .unwrap();
+        }
+
+        assert_eq!(quadtree.points.len(), 4);
+        assert!(quadtree.subtrees.is_none());
+    }
+
+    #[test]
+    fn test_quadtree_insert_above_capacity() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(4).unwrap());
+
+        for i in 0..4 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i, 0]), "data".to_string()))
+                .unwrap();
+        }
+
+        assert_eq!(quadtree.points.len(), 4);
+        assert!(quadtree.subtrees.is_none());
+
+        // Insert one more point to trigger subdivision
+        quadtree
+            .insert(TestStruct(
+                Point::new(&[5, 5]),
+                "data_subdivided".to_string(),
+            ))
+            .unwrap();
+
+        // Check that the quadtree has subdivided
+        assert!(quadtree.subtrees.is_some());
+        let subtrees = quadtree.subtrees.as_ref().unwrap();
+        assert_eq!(subtrees.len(), 4);
+
+        // The root's points moved down alongside the new point, which went into only 1 subtree
+        assert!(quadtree.points.is_empty());
+        let subtree_total_points: usize = subtrees.iter().map(|st| st.points.len()).sum();
+        assert_eq!(subtree_total_points, 5);
+        assert_eq!(
+            subtrees
+                .iter()
+                .filter(|subtree| subtree
+                    .points
+                    .iter()
+                    .any(|p| p.item().1 == "data_subdivided"))
+                .count(),
+            1
+        );
+
+        assert!(quadtree.regions().len() > 1);
+    }
+
+    #[test]
+    fn test_quadtree_query() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(4).unwrap());
+
+        for i in 0..4 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i, 0]), "data".to_string()))
+                .unwrap();
+        }
+
+        // Construct query region that should only contain only the first two points
+        let query_region = Region::new(&[
+            Interval::try_new(0.0, 2.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+
+        let results: Vec<_> = quadtree.query(&query_region).collect();
+
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn test_quadtree_query_subdivided() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        // Capacity of 2 will ensure lots of subdivision when inserting 10 items
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+
+        for i in 0..10 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i, 0]), "data".to_string()))
+                .unwrap();
+        }
+
+        // Construct query region
+        let query_region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+
+        let results: Vec<_> = quadtree.query(&query_region).collect();
+
+        assert_eq!(results.len(), 10);
+    }
+
+    #[test]
+    fn test_quadtree_query_partition() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+
+        for i in 0..10 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i, i]), "data".to_string()))
+                .unwrap();
+        }
+
+        // Query covers points 0..6, the partition highlights points 2..4
+        let query_region = Region::new(&[
+            Interval::try_new(0.0, 6.0).unwrap(),
+            Interval::try_new(0.0, 6.0).unwrap(),
+        ]);
+        let partition = Region::new(&[
+            Interval::try_new(2.0, 4.0).unwrap(),
+            Interval::try_new(2.0, 4.0).unwrap(),
+        ]);
+
+        let (inside, outside) = quadtree.query_partition(&query_region, &partition);
+        assert_eq!(inside.len(), 2);
+        assert_eq!(outside.len(), 4);
+        assert!(inside.iter().all(|item| partition.contains(item.point())));
+        assert!(outside.iter().all(|item| !partition.contains(item.point())));
+    }
+
+    #[test]
+    fn test_quadtree_leaf_cells_touched() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(1).unwrap());
+        for i in 0..10 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i, i]), "data".to_string()))
+                .unwrap();
+        }
+
+        let query_region = Region::new(&[
+            Interval::try_new(0.0, 3.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let expected = quadtree
+            .leaves()
+            .iter()
+            .filter(|leaf| leaf.region.intersects(&query_region))
+            .count();
+        assert!(expected > 1);
+        assert_eq!(quadtree.leaf_cells_touched(&query_region), expected);
+
+        // The whole region touches every leaf
+        assert_eq!(
+            quadtree.leaf_cells_touched(&region),
+            quadtree.leaves().len()
+        );
+    }
+
+    #[test]
+    fn test_quadtree_repair() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(1).unwrap());
+        quadtree
+            .insert(TestStruct(Point::new(&[1, 1]), "root".to_string()))
+            .unwrap();
+        quadtree
+            .insert(TestStruct(Point::new(&[2, 2]), "child".to_string()))
+            .unwrap();
+        assert_eq!(quadtree.repair(), 0);
+
+        // Deliberately misplace a point into the [0, 5) x [5, 10) subtree
+        let subtree = &mut quadtree.subtrees.as_mut().unwrap()[1];
+        assert!(subtree.points.is_empty());
+        subtree
+            .points
+            .push(TestStruct(Point::new(&[8, 8]), "misplaced".to_string()));
+
+        assert_eq!(quadtree.repair(), 1);
+        assert!(quadtree.subtrees.as_ref().unwrap()[1].points.is_empty());
+        assert_eq!(quadtree.repair(), 0);
+
+        // The repaired point is found by a query over its true location
+        let query_region = Region::new(&[
+            Interval::try_new(5.0, 10.0).unwrap(),
+            Interval::try_new(5.0, 10.0).unwrap(),
+        ]);
+        let results: Vec<_> = quadtree.query(&query_region).collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1, "misplaced");
+    }
+
+    #[test]
+    fn test_quadtree_query_mut() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        for i in 0..10 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i, 0]), "data".to_string()))
+                .unwrap();
+        }
+
+        let query_region = Region::new(&[
+            Interval::try_new(0.0, 5.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        for item in quadtree.query_mut(&query_region) {
+            item.1 = "visited".to_string();
+        }
+
+        let visited = quadtree
+            .query(&region)
+            .filter(|item| item.1 == "visited")
+            .count();
+        assert_eq!(visited, 5);
+        assert!(
+            quadtree
+                .query(&query_region)
+                .all(|item| item.1 == "visited")
+        );
+    }
+
+    #[test]
+    fn test_quadtree_clone_structure_empty() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(1).unwrap());
+        for i in 0..10 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i, i]), "data".to_string()))
+                .unwrap();
+        }
+
+        let empty = quadtree.clone_structure_empty();
+        assert_eq!(empty.regions(), quadtree.regions());
+        assert_eq!(empty.query(&region).count(), 0);
+        assert!(empty.leaves().iter().all(|leaf| leaf.points.is_empty()));
+        assert_eq!(empty.points.capacity(), quadtree.points.capacity());
+    }
+
+    #[test]
+    fn test_quadtree_query_into() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        for i in 0..10 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i, i]), format!("P{}", i)))
+                .unwrap();
+        }
+
+        let query_region = Region::new(&[
+            Interval::try_new(0.0, 5.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut buffer = Vec::new();
+        quadtree.query_into(&query_region, &mut buffer);
+        let names: Vec<_> = buffer.iter().map(|item| item.1.clone()).sorted().collect();
+        assert_eq!(names, vec!["P0", "P1", "P2", "P3", "P4"]);
+
+        // The buffer is appended to, not cleared
+        let circle_query = DistanceQuery::new(&Point::new(&[9.0, 9.0]), 1.0);
+        quadtree.query_into(&circle_query, &mut buffer);
+        assert_eq!(buffer.len(), 6);
+    }
+
+    #[test]
+    fn test_quadtree_query_is_lazy() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 100.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(4).unwrap());
+        for i in 0..100 {
+            quadtree.insert(Point::new(&[i, i])).unwrap();
+        }
+
+        let checked = std::cell::Cell::new(0);
+        let query = crate::query::FnQuery::new(region.clone(), |_: &Point<2>| {
+            checked.set(checked.get() + 1);
+            true
+        });
+        assert!(quadtree.query(&query).next().is_some());
+        assert!(checked.get() < 100);
+    }
+
+    #[test]
+    fn test_quadtree_query_heatmap() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        // Three points in the bottom-left cell, one in the top-left, one outside the query
+        for (x, y) in [(1, 1), (2, 3), (3, 3), (1, 7), (9, 9)] {
+            quadtree
+                .insert(TestStruct(Point::new(&[x, y]), "data".to_string()))
+                .unwrap();
+        }
+
+        let query_region = Region::new(&[
+            Interval::try_new(0.0, 8.0).unwrap(),
+            Interval::try_new(0.0, 8.0).unwrap(),
+        ]);
+        let heatmap = quadtree
+            .query_heatmap(&query_region, NonZero::new(2).unwrap())
+            .unwrap();
+        // Cells are [x0 y0, x1 y0, x0 y1, x1 y1]
+        assert_eq!(heatmap, vec![3, 0, 1, 0]);
+        assert_eq!(heatmap.iter().sum::<usize>(), 4);
+    }
+
+    #[test]
+    fn test_quadtree_query_heatmap_too_many_cells() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let quadtree: QuadTree<3, Point<3>> = QuadTree::new(&region, NonZero::new(2).unwrap());
+        // (2^22)^3 cells overflows the count
+        let overflowing = NonZero::new(1 << 22).unwrap();
+        assert!(quadtree.query_heatmap(&region, overflowing).is_err());
+        // (2^21)^3 cells fits in a usize, but is far too many to allocate
+        let too_large = NonZero::new(1 << 21).unwrap();
+        assert!(quadtree.query_heatmap(&region, too_large).is_err());
+    }
+
+    #[test]
+    fn test_quadtree_depth_and_node_count() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(1).unwrap());
+        assert_eq!(quadtree.depth(), 1);
+        assert_eq!(quadtree.node_count(), 1);
+
+        quadtree
+            .insert(TestStruct(Point::new(&[1, 1]), "data".to_string()))
+            .unwrap();
+        assert_eq!(quadtree.depth(), 1);
+        assert_eq!(quadtree.node_count(), 1);
+
+        // Second point, in another quarter, subdivides the root
+        quadtree
+            .insert(TestStruct(Point::new(&[6, 6]), "data".to_string()))
+            .unwrap();
+        assert_eq!(quadtree.depth(), 2);
+        assert_eq!(quadtree.node_count(), 5);
+
+        // Third point in the same quarter subdivides again
+        quadtree
+            .insert(TestStruct(Point::new(&[3, 3]), "data".to_string()))
+            .unwrap();
+        assert_eq!(quadtree.depth(), 3);
+        assert_eq!(quadtree.node_count(), 9);
+    }
+
+    #[test]
+    fn test_quadtree2_from_bounds() -> Result<()> {
+        use crate::point::Point2;
+
+        let mut quadtree: QuadTree2<Point2> =
+            QuadTree2::from_bounds(0.0, -5.0, 10.0, 5.0, NonZero::new(2).unwrap())?;
+        let generic = QuadTree::new(
+            &Region::new(&[Interval::try_new(0.0, 10.0)?, Interval::try_new(-5.0, 5.0)?]),
+            NonZero::new(2).unwrap(),
+        );
+        assert_eq!(quadtree, generic);
+
+        quadtree.extend([
+            Point2::xy(1.0, -4.0),
+            Point2::xy(9.0, 4.0),
+            Point2::xy(5.0, 0.0),
+        ]);
+        assert_eq!(quadtree.query_range(&[0.0, -5.0], &[6.0, 1.0])?.len(), 2);
+        assert!(quadtree.insert(Point2::xy(1.0, 6.0)).is_err());
+
+        assert!(
+            QuadTree2::<Point2>::from_bounds(0.0, 5.0, 10.0, 5.0, NonZero::new(2).unwrap())
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_quadtree_insert_tracked() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(1).unwrap());
+        assert!(
+            quadtree
+                .insert_tracked(Point::new(&[9, 9]))
+                .unwrap()
+                .is_empty()
+        );
+
+        // Each point closer to the origin forces another subdivision of the bottom-left corner
+        let mut path = Vec::new();
+        for value in [4.0, 2.0, 1.0, 0.5] {
+            path = quadtree
+                .insert_tracked(Point::new(&[value, value]))
+                .unwrap();
+        }
+        assert_eq!(path, vec![0, 0, 0, 0]);
+        assert_eq!(path.len() + 1, quadtree.depth());
+
+        // Following the path leads to the node holding the point
+        let node = path.iter().fold(&quadtree, |node, &index| {
+            &node.children().expect("path only descends into subtrees")[index]
+        });
+        assert!(node.points.contains(&Point::new(&[0.5, 0.5])));
+
+        assert!(quadtree.insert_tracked(Point::new(&[11.0, 11.0])).is_err());
+    }
+
+    #[test]
+    fn test_quadtree_contains_point() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(1).unwrap());
+        for i in 0..5 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i, i]), "data".to_string()))
+                .unwrap();
+        }
+
+        assert!(quadtree.contains_point(&Point::new(&[0, 0])));
+        assert!(quadtree.contains_point(&Point::new(&[3, 3])));
+        assert!(!quadtree.contains_point(&Point::new(&[3.0, 3.1])));
+        assert!(!quadtree.contains_point(&Point::new(&[20, 20])));
+    }
+
+    #[test]
+    fn test_quadtree_retain() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        for i in 0..10 {
+            let prefix = if i < 2 { "keep" } else { "drop" };
+            quadtree
+                .insert(TestStruct(Point::new(&[i, i]), format!("{}-{}", prefix, i)))
+                .unwrap();
+        }
+        assert!(quadtree.subtrees.is_some());
+
+        let mut seen = 0;
+        quadtree.retain(|item| {
+            seen += 1;
+            item.1.starts_with("keep")
+        });
+        assert_eq!(seen, 10);
+
+        // The kept items fit in the root, so all subtrees collapse
+        assert!(quadtree.subtrees.is_none());
+        let results: Vec<_> = quadtree.query(&region).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|item| item.1.starts_with("keep")));
+
+        // The tree is still valid for inserts and queries
+        quadtree
+            .insert(TestStruct(Point::new(&[9, 9]), "new".to_string()))
+            .unwrap();
+        assert_eq!(quadtree.query(&region).count(), 3);
+    }
+
+    #[test]
+    fn test_quadtree_count() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 100.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(4).unwrap());
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
+        for _ in 0..1000 {
+            let x = rng.random_range(0.0..100.0);
+            let y = rng.random_range(0.0..100.0);
+            quadtree
+                .insert(TestStruct(Point::new(&[x, y]), "data".to_string()))
+                .unwrap();
+        }
+
+        let query_region = Region::new(&[
+            Interval::try_new(10.0, 60.0).unwrap(),
+            Interval::try_new(25.0, 40.0).unwrap(),
+        ]);
+        assert_eq!(
+            quadtree.count(&query_region),
+            quadtree.query(&query_region).count()
+        );
+
+        let circle_query = DistanceQuery::new(&Point::new(&[50.0, 50.0]), 30.0);
+        assert_eq!(
+            quadtree.count(&circle_query),
+            quadtree.query(&circle_query).count()
+        );
+        assert_eq!(quadtree.count(&region), 1000);
+    }
+
+    #[test]
+    fn test_quadtree_for_each_match() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 100.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(4).unwrap());
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
+        for _ in 0..1000 {
+            let x = rng.random_range(0.0..100.0);
+            let y = rng.random_range(0.0..100.0);
+            quadtree.insert(Point::new(&[x, y])).unwrap();
+        }
+
+        let circle_query = DistanceQuery::new(&Point::new(&[50.0, 50.0]), 30.0);
+        let mut calls = 0;
+        quadtree.for_each_match(&circle_query, |point| {
+            assert!(circle_query.contains(point));
+            calls += 1;
+        });
+        assert!(calls > 0);
+        assert_eq!(calls, quadtree.query(&circle_query).count());
+
+        let mut calls = 0;
+        quadtree.for_each_match(&region, |_| calls += 1);
+        assert_eq!(calls, 1000);
+    }
+
+    #[test]
+    fn test_quadtree_insert_growing() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        for i in 0..5 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i, i]), "data".to_string()))
+                .unwrap();
+        }
+
+        let far_away = Point::new(&[100, -50]);
+        assert!(
+            quadtree
+                .insert(TestStruct(far_away, "far".to_string()))
+                .is_err()
+        );
+        quadtree
+            .insert_growing(TestStruct(far_away, "far".to_string()))
+            .unwrap();
+        assert!(quadtree.region.contains(&far_away));
+
+        // The old region was kept as a subtree
+        assert!(quadtree.regions().contains(&region));
+
+        // Both the far point and the original points are queryable
+        let far_query = DistanceQuery::new(&far_away, 1.0);
+        let results: Vec<_> = quadtree.query(&far_query).collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1, "far");
+        assert_eq!(quadtree.query(&region).count(), 5);
+
+        // Points that are not finite can never be covered
+        let infinite = Point::new(&[f64::INFINITY, 0.0]);
+        assert!(
+            quadtree
+                .insert_growing(TestStruct(infinite, "inf".to_string()))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_quadtree_nearest_one() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(1).unwrap());
+        assert!(quadtree.nearest_one(&Point::new(&[1, 1])).is_none());
+
+        for (x, y, name) in [
+            (9.0, 9.0, "far"),
+            (1.0, 1.0, "bottom-left"),
+            (5.5, 4.0, "just-right"),
+            (1.0, 9.0, "top-left"),
+        ] {
+            quadtree
+                .insert(TestStruct(Point::new(&[x, y]), name.to_string()))
+                .unwrap();
+        }
+
+        // Query from the bottom-left quarter, the closest point is over the boundary to the right
+        let target = Point::new(&[4.5, 4.0]);
+        let nearest = quadtree.nearest_one(&target).unwrap();
+        assert_eq!(nearest.1, "just-right");
+        let brute_force = quadtree
+            .query(&region)
+            .map(|item| item.point().distance(&target))
+            .fold(f64::INFINITY, f64::min);
+        assert_eq!(nearest.point().distance(&target), brute_force);
+
+        assert_eq!(
+            quadtree.nearest_one(&Point::new(&[0, 10])).unwrap().1,
+            "top-left"
+        );
+    }
+
+    #[test]
+    fn test_quadtree_insert_many() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+
+        let items = [(1, 1), (20, 1), (3, 3), (5, 5), (-1, 4), (9, 9)]
+            .into_iter()
+            .map(|(x, y)| TestStruct(Point::new(&[x, y]), "data".to_string()));
+        let results = quadtree.insert_many(items);
+
+        let failed: Vec<_> = results.iter().positions(|result| result.is_err()).collect();
+        assert_eq!(failed, vec![1, 4]);
+        assert_eq!(quadtree.query(&region).count(), 4);
+    }
+
+    #[test]
+    fn test_quadtree_from_iterator() {
+        let points: Vec<Point<2>> = (0..50).map(|i| Point::new(&[i, 100 - i])).collect();
+        let quadtree: QuadTree<2, _> = points.clone().into_iter().collect();
+
+        assert!(quadtree.subtrees.is_some());
+        for point in &points {
+            assert!(quadtree.contains_point(point));
+        }
+
+        let query_region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 101.0).unwrap(),
+        ]);
+        assert_eq!(quadtree.query(&query_region).count(), 10);
+    }
+
+    #[test]
+    fn test_dyn_quadtree() {
+        struct City {
+            location: Point<2>,
+            name: &'static str,
+        }
+        impl Storable<City, 2> for City {
+            fn point(&self) -> &Point<2> {
+                &self.location
+            }
+
+            fn item(&self) -> &City {
+                self
+            }
+        }
+
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree: DynQuadTree<2> = QuadTree::new(&region, NonZero::new(2).unwrap());
+        quadtree
+            .insert(Box::new(City {
+                location: Point::new(&[1.0, 1.0]),
+                name: "Leeds",
+            }))
+            .unwrap();
+        quadtree.insert(Box::new(Point::new(&[2.0, 2.0]))).unwrap();
+        quadtree
+            .insert(Box::new(Entry::new(Point::new(&[3.0, 3.0]), 42_u32)))
+            .unwrap();
+        quadtree
+            .insert(Box::new(City {
+                location: Point::new(&[8.0, 8.0]),
+                name: "York",
+            }))
+            .unwrap();
+
+        let query_region = Region::new(&[
+            Interval::try_new(0.0, 5.0).unwrap(),
+            Interval::try_new(0.0, 5.0).unwrap(),
+        ]);
+        let found: Vec<_> = quadtree.query(&query_region).collect();
+        assert_eq!(found.len(), 3);
+
+        let cities: Vec<_> = found
+            .iter()
+            .filter_map(|item| item.downcast_ref::<City>())
+            .map(|city| city.name)
+            .collect();
+        assert_eq!(cities, vec!["Leeds"]);
+        let points: Vec<_> = found
+            .iter()
+            .filter_map(|item| item.downcast_ref::<Point<2>>())
+            .collect();
+        assert_eq!(points, vec![&Point::new(&[2.0, 2.0])]);
+        let entries: Vec<_> = found
+            .iter()
+            .filter_map(|item| item.downcast_ref::<Entry<2, u32>>())
+            .map(|entry| *entry.value())
+            .collect();
+        assert_eq!(entries, vec![42]);
+    }
+
+    #[test]
+    fn test_quadtree_default() {
+        let mut quadtree: QuadTree<3, Point<3>> = QuadTree::default();
+        assert!(quadtree.insert(Point::new(&[0.5, 0.25, 0.0])).is_ok());
+        assert!(quadtree.insert(Point::new(&[1.0, 1.0, 1.0])).is_ok());
+        assert!(quadtree.insert(Point::new(&[1.5, 0.5, 0.5])).is_err());
+        assert!(quadtree.insert(Point::new(&[0.5, -0.1, 0.5])).is_err());
+        assert!(quadtree.contains_point(&Point::new(&[0.5, 0.25, 0.0])));
+        assert!(!quadtree.contains_point(&Point::new(&[1.5, 0.5, 0.5])));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_quadtree_from_empty_iterator() {
+        let _: QuadTree<2, Point<2>> = Vec::new().into_iter().collect();
+    }
+
+    #[test]
+    fn test_quadtree_many_points() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 100.0).unwrap(),
+        ]);
+
+        // Capacity of 100 will ensure lots of subdivision when inserting 100,000 items
+        let mut quadtree = QuadTree::new(&region, NonZero::new(100).unwrap());
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
+
+        for _ in 0..100_000 {
+            // Choose random x and y coordinates
+            let x = rng.random_range(0..100);
+            let y = rng.random_range(0..100);
+            quadtree
+                .insert(TestStruct(Point::new(&[x, y]), "data".to_string()))
+                .unwrap();
+        }
+
+        // Construct query region
+        let query_region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+
+        let results: Vec<_> = quadtree.query(&query_region).collect();
+        dbg!(&results.len());
+        assert!((900..=1100).contains(&results.len()));
+    }
+
+    #[test]
+    fn test_quadtree_coincident_points() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 100.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(1).unwrap());
+        let point = Point::new(&[12.5, 60.0]);
+        for i in 0..1000 {
+            quadtree
+                .insert(TestStruct(point, format!("P{}", i)))
+                .unwrap();
+        }
+
+        // All kept in an overflowing root, rather than subdividing towards the float limit
+        assert_eq!(quadtree.node_count(), 1);
+        assert_eq!(quadtree.query(&region).count(), 1000);
+
+        // A distinct point still subdivides as usual
+        quadtree
+            .insert(TestStruct(Point::new(&[90.0, 10.0]), "other".to_string()))
+            .unwrap();
+        assert!(!quadtree.is_leaf());
+        assert_eq!(quadtree.query(&region).count(), 1001);
+    }
+
+    #[test]
+    fn test_quadtree_coincident_points_with_max_depth() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 100.0).unwrap(),
+        ]);
+        let mut quadtree =
+            QuadTree::with_max_depth(&region, NonZero::new(1).unwrap(), NonZero::new(4).unwrap());
+        for i in 0..50 {
+            quadtree
+                .insert(TestStruct(Point::new(&[12.5, 60.0]), format!("P{}", i)))
+                .unwrap();
+        }
+
+        // The depth cap bounds the subdivision, so they still overflow a leaf at the cap
+        assert_eq!(quadtree.depth(), 4);
+        assert_eq!(quadtree.query(&region).count(), 50);
+
+        // Distinct but very close points also stop at the cap
+        for i in 0..50 {
+            let offset = i as f64 * 1e-9;
+            quadtree
+                .insert(TestStruct(
+                    Point::new(&[1.0 + offset, 1.0]),
+                    format!("Q{}", i),
+                ))
+                .unwrap();
+        }
+        assert_eq!(quadtree.depth(), 4);
+        assert_eq!(quadtree.query(&region).count(), 100);
+    }
+
+    #[test]
+    fn test_quadtree_single_point_interval() {
+        const COUNT: usize = 10;
+        // Create a quadtree where the region is a single point
+        let region = Region::new(&[
+            Interval::try_new(1.0, 1.0 + f64::EPSILON).unwrap(),
+            Interval::try_new(1.0, 1.0 + f64::EPSILON).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(1).unwrap());
+
+        // Insert 10 points into the quadtree
+        for i in 0..COUNT {
+            quadtree
+                .insert(TestStruct(Point::new(&[1.0, 1.0]), format!("P{}", i)))
+                .unwrap();
+        }
+
+        // Query the quadtree
+        let results: Vec<_> = quadtree.query(&region).collect();
+        assert_eq!(results.len(), 10);
+        // Assert there are 10 unique strings
+        let unique_results: Vec<_> = results.iter().map(|item| item.item().1.clone()).collect();
+        assert_eq!(
+            unique_results
+                .into_iter()
+                .unique()
+                .collect::<Vec<_>>()
+                .len(),
+            COUNT
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_quadtree_par_query() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 1000.0).unwrap(),
+            Interval::try_new(0.0, 1000.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(10).unwrap());
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
+        for _ in 0..100_000 {
+            let point = Point::new(&[rng.random_range(0.0..1000.0), rng.random_range(0.0..1000.0)]);
+            quadtree.insert(point).unwrap();
+        }
+
+        let search_region = Region::new(&[
+            Interval::try_new(550.0, 600.0).unwrap(),
+            Interval::try_new(0.0, 200.0).unwrap(),
+        ]);
+        let mut sequential: Vec<_> = quadtree.query(&search_region).collect();
+        let mut parallel = quadtree.par_query(&search_region);
+        sequential.sort();
+        parallel.sort();
+        assert!(!parallel.is_empty());
+        assert_eq!(parallel, sequential);
+
+        let circle_query = DistanceQuery::new(&Point::new(&[500.0, 500.0]), 100.0);
+        assert_eq!(
+            quadtree.par_query(&circle_query).len(),
+            quadtree.query(&circle_query).count()
+        );
+    }
+
+    #[test]
+    fn test_quadtree_max_depth() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree =
+            QuadTree::with_max_depth(&region, NonZero::new(2).unwrap(), NonZero::new(3).unwrap());
+
+        for i in 0..100 {
+            quadtree
+                .insert(TestStruct(Point::new(&[1.0, 1.0]), format!("P{}", i)))
+                .unwrap();
+        }
+
+        assert_eq!(quadtree.depth(), 3);
+        let results: Vec<_> = quadtree.query(&region).collect();
+        assert_eq!(results.len(), 100);
+        let overflowing = quadtree
+            .leaves()
+            .into_iter()
+            .find(|leaf| leaf.points.len() > 2)
+            .unwrap();
+        assert_eq!(overflowing.depth, 3);
+        assert_eq!(overflowing.points.len(), 100);
+    }
+
+    #[test]
+    fn test_quadtree_builder() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut built = QuadTreeBuilder::new()
+            .region(region.clone())
+            .max_points(NonZero::new(2).unwrap())
+            .build()
+            .unwrap();
+        let mut constructed = QuadTree::new(&region, NonZero::new(2).unwrap());
+        for i in 0..10 {
+            built.insert(Point::new(&[i, i])).unwrap();
+            constructed.insert(Point::new(&[i, i])).unwrap();
+        }
+        assert_eq!(built, constructed);
+        assert!(built.insert(Point::new(&[20, 20])).is_err());
+
+        // A region is required
+        assert!(QuadTreeBuilder::<2, Point<2>>::new().build().is_err());
+
+        // Max depth and auto grow are passed through
+        let mut growing = QuadTreeBuilder::new()
+            .region(region.clone())
+            .max_points(NonZero::new(1).unwrap())
+            .max_depth(NonZero::new(2).unwrap())
+            .auto_grow(true)
+            .build()
+            .unwrap();
+        for _ in 0..10 {
+            growing.insert(Point::new(&[1, 1])).unwrap();
+        }
+        assert_eq!(growing.depth(), 2);
+        growing.insert(Point::new(&[20, 20])).unwrap();
+        assert!(growing.contains_point(&Point::new(&[20, 20])));
+        assert!(growing.depth() <= 2);
+        assert_eq!(growing.query(&region).count(), 10);
+    }
+
+    /// Whether every node with subtrees holds no points itself, recursively
+    fn internal_nodes_empty<V: Storable<V, 2>>(node: &QuadTree<2, V>) -> bool {
+        node.children().is_none_or(|children| {
+            node.points.is_empty() && children.iter().all(internal_nodes_empty)
+        })
+    }
+
+    #[test]
+    fn test_quadtree_builder_leaves_only() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(11);
+        let region = Region::new(&[
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 100.0).unwrap(),
+        ]);
+        let mut leaves_only = QuadTree::new(&region, NonZero::new(4).unwrap());
+        let mut keeping = QuadTreeBuilder::new()
+            .region(region.clone())
+            .max_points(NonZero::new(4).unwrap())
+            .leaves_only(false)
+            .build()
+            .unwrap();
+        for _ in 0..500 {
+            let point = region.sample_point(&mut rng);
+            leaves_only.insert(point).unwrap();
+            keeping.insert(point).unwrap();
+        }
+        // Coincident points and the upper corner are pushed down too
+        for point in [[10.0, 10.0], [10.0, 10.0], [100.0, 100.0]] {
+            leaves_only.insert(Point::new(&point)).unwrap();
+            keeping.insert(Point::new(&point)).unwrap();
+        }
+
+        assert!(!leaves_only.is_leaf());
+        assert!(internal_nodes_empty(&leaves_only));
+        assert!(!internal_nodes_empty(&keeping));
+
+        let query = Point::new(&[30.0, 60.0]).to_distance_based_query(25.0);
+        assert_eq!(
+            leaves_only.query_sorted(&query),
+            keeping.query_sorted(&query)
+        );
+        assert_eq!(leaves_only.query(&region).count(), 502);
+        let everything = crate::query::InclusiveRegion::new(&region);
+        assert_eq!(
+            leaves_only.query_sorted(&everything),
+            keeping.query_sorted(&everything)
+        );
+    }
+
+    #[test]
+    fn test_quadtree_set_max_points() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(4).unwrap());
+        for i in 0..3 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i * 3, i * 3]), "data".to_string()))
+                .unwrap();
+        }
+        assert!(quadtree.subtrees.is_none());
+
+        // With the old maximum of 4 this insert would fit in the root
+        quadtree.set_max_points(NonZero::new(2).unwrap());
+        quadtree
+            .insert(TestStruct(Point::new(&[9, 9]), "data".to_string()))
+            .unwrap();
+        assert!(quadtree.subtrees.is_some());
+        assert!(quadtree.points.is_empty());
+        assert!(
+            quadtree
+                .subtrees
+                .iter()
+                .flatten()
+                .all(|subtree| subtree.max_points.get() == 2)
+        );
+
+        // Raising the maximum lets a node hold more points before subdividing
+        quadtree.set_max_points(NonZero::new(10).unwrap());
+        for i in 3..10 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i, i]), "data".to_string()))
+                .unwrap();
+        }
+        assert_eq!(quadtree.depth(), 2);
+        assert_eq!(quadtree.query(&region).count(), 11);
+    }
+
+    #[test]
+    fn test_quadtree_query_with_points() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        for i in 0..10 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i, 9 - i]), format!("P{}", i)))
+                .unwrap();
+        }
+
+        let circle_query = DistanceQuery::new(&Point::new(&[2.0, 7.0]), 2.0);
+        let results: Vec<_> = quadtree.query_with_points(&circle_query).collect();
+        assert_eq!(results.len(), quadtree.query(&circle_query).count());
+        assert!(!results.is_empty());
+        for (point, item) in results {
+            assert!(circle_query.contains(point));
+            assert_eq!(point, item.point());
+        }
+    }
+
+    #[test]
+    fn test_quadtree_region_query_matches_brute_force() {
+        // Region queries skip per-point checks on nodes they contain, which must not change the results
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+        let region = Region::new(&[
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 100.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(4).unwrap());
+        let points: Vec<Point<2>> = (0..1000).map(|_| region.sample_point(&mut rng)).collect();
+        quadtree.extend(points.iter().copied());
+
+        let query = Region::new(&[
+            Interval::try_new(0.0, 75.0).unwrap(),
+            Interval::try_new(12.5, 50.0).unwrap(),
+        ]);
+        assert!(
+            quadtree
+                .leaves()
+                .iter()
+                .any(|leaf| query.contains_region(&leaf.region))
+        );
+
+        let results: Vec<_> = quadtree.query(&query).copied().sorted().collect();
+        let expected: Vec<_> = points
+            .into_iter()
+            .filter(|point| query.contains(point))
+            .sorted()
+            .collect();
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn test_quadtree_query_cloned() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        for i in 0..10 {
+            quadtree
+                .insert(TestStruct(Point::new(&[i, 9 - i]), format!("P{}", i)))
+                .unwrap();
+        }
+
+        let query = DistanceQuery::new(&Point::new(&[5.0, 5.0]), 3.0);
+        let borrowed: Vec<String> = quadtree
+            .query(&query)
+            .map(|item| item.1.clone())
+            .sorted()
+            .collect();
+        let cloned = quadtree.query_cloned(&query);
+        drop(quadtree);
+
+        let cloned: Vec<String> = cloned.into_iter().map(|item| item.1).sorted().collect();
+        assert!(!cloned.is_empty());
+        assert_eq!(cloned, borrowed);
+    }
+
+    #[test]
+    fn test_quadtree_query_sorted() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let points: Vec<Point<2>> = (0..10)
+            .cartesian_product(0..10)
+            .map(|(x, y)| Point::new(&[x, y]))
+            .collect();
 
-        let subtree_iter = self.subtrees.iter().flat_map(|subtrees| {
-            subtrees
-                .iter()
-                .filter(|subtree| subtree.region.intersects(query.region()))
-                .flat_map(|subtree| subtree.query(query))
-        });
+        let mut forwards = QuadTree::new(&region, NonZero::new(2).unwrap());
+        forwards.extend(points.iter().copied());
+        let mut backwards = QuadTree::new(&region, NonZero::new(2).unwrap());
+        backwards.extend(points.iter().rev().copied());
 
-        Box::new(my_iter.chain(subtree_iter))
+        let query = DistanceQuery::new(&Point::new(&[4.0, 6.0]), 3.0);
+        let sorted = forwards.query_sorted(&query);
+        assert!(sorted.len() > 1);
+        assert!(sorted.is_sorted());
+        assert_eq!(sorted, backwards.query_sorted(&query));
     }
 
-    /// Returns all regions
-    #[cfg(feature = "nannou")]
-    pub fn regions(&self) -> Vec<Region<N>> {
-        let mut regions = vec![self.region.clone()];
-
-        regions.extend(
-            self.subtrees
-                .iter()
-                .flat_map(|subtrees| subtrees.iter().flat_map(|subtree| subtree.regions())),
+    #[test]
+    fn test_quadtree_query_by_distance() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        quadtree.extend(
+            (0..10)
+                .cartesian_product(0..10)
+                .map(|(x, y)| Point::new(&[x, y])),
         );
 
-        regions
+        let center = Point::new(&[4.5, 6.0]);
+        let results = quadtree.query_by_distance(&center, 2.0);
+        assert_eq!(
+            results.len(),
+            quadtree.count(&DistanceQuery::new(&center, 2.0))
+        );
+        assert!(results.iter().map(|(_, distance)| distance).is_sorted());
+        assert!(results.iter().all(|(_, distance)| *distance <= 2.0));
+        for (point, distance) in &results {
+            assert_eq!(point.distance(&center), *distance);
+        }
+        assert_eq!(results[0].1, 0.5);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use itertools::Itertools;
-    use rand::{Rng, SeedableRng};
+    #[test]
+    fn test_quadtree_split_longest_axis() {
+        use crate::subdivision::SplitLongestAxis;
 
-    use super::*;
-    use crate::{interval::Interval, point::Point, query::DistanceQuery};
+        let region = Region::new(&[
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTreeBuilder::new()
+            .region(region.clone())
+            .max_points(NonZero::new(2).unwrap())
+            .strategy::<SplitLongestAxis>()
+            .build()
+            .unwrap();
+        let points: Vec<Point<2>> = (0..50).map(|i| Point::new(&[i * 2, i % 10])).collect();
+        quadtree.extend(points.iter().copied());
 
-    pub struct TestStruct(Point<2>, String);
-    impl Storable<TestStruct, 2> for TestStruct {
-        fn point(&self) -> &Point<2> {
-            &self.0
-        }
+        assert_eq!(quadtree.children().unwrap().len(), 2);
 
-        fn item(&self) -> &Self {
-            self
-        }
+        let query = Region::new(&[
+            Interval::try_new(20.0, 60.0).unwrap(),
+            Interval::try_new(2.0, 8.0).unwrap(),
+        ]);
+        let expected = points.iter().filter(|point| query.contains(point)).count();
+        assert_eq!(quadtree.query(&query).count(), expected);
     }
 
     #[test]
-    fn test_quadtree_insert_outside_region() {
+    fn test_quadtree_leaf_densities() {
         let region = Region::new(&[
-            Interval::try_new(0.0, 10.0).unwrap(),
-            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 100.0).unwrap(),
         ]);
         let mut quadtree = QuadTree::new(&region, NonZero::new(4).unwrap());
+        // A few spread out points, then a cluster in one corner
+        quadtree.extend([
+            Point::new(&[10, 10]),
+            Point::new(&[60, 10]),
+            Point::new(&[10, 60]),
+            Point::new(&[60, 60]),
+        ]);
+        quadtree.extend((0..4).map(|i| Point::new(&[90.0 + i as f64, 90.0])));
 
-        let point_outside = TestStruct(Point::new(&[11, 5]), "data".to_string());
-        assert!(quadtree.insert(point_outside).is_err());
+        // The upper right quarter splits again to separate (60, 60) from the cluster
+        let densities = quadtree.leaf_densities();
+        assert_eq!(densities.len(), 7);
+        let (densest, count) = densities.iter().max_by_key(|(_, count)| *count).unwrap();
+        assert_eq!(*count, 4);
+        assert!(densest.contains(&Point::new(&[90, 90])));
+        assert_eq!(densities.iter().map(|(_, count)| count).sum::<usize>(), 8);
     }
 
     #[test]
-    fn test_quadtree_initialise() {
+    fn test_quadtree_is_leaf_and_children() {
         let region = Region::new(&[
             Interval::try_new(0.0, 10.0).unwrap(),
             Interval::try_new(0.0, 10.0).unwrap(),
         ]);
-        let _: QuadTree<2, TestStruct> = QuadTree::new(&region, NonZero::new(4).unwrap());
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        assert!(quadtree.is_leaf());
+        assert!(quadtree.children().is_none());
+
+        quadtree.extend([
+            Point::new(&[1, 1]),
+            Point::new(&[6, 6]),
+            Point::new(&[1, 6]),
+        ]);
+        assert!(!quadtree.is_leaf());
+        let children = quadtree.children().unwrap();
+        assert_eq!(children.len(), 4);
+        assert!(children.iter().all(|child| child.is_leaf()));
     }
 
     #[test]
-    fn test_quadtree_insert_below_capacity() {
+    fn test_quadtree_memory_footprint() {
         let region = Region::new(&[
             Interval::try_new(0.0, 10.0).unwrap(),
             Interval::try_new(0.0, 10.0).unwrap(),
         ]);
-        let mut quadtree = QuadTree::new(&region, NonZero::new(4).unwrap());
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        quadtree.extend([Point::new(&[1, 1]), Point::new(&[2, 2])]);
+        let before = quadtree.memory_footprint();
+        assert!(before >= 2 * size_of::<Point<2>>());
 
-        for i in 0..4 {
-            quadtree
-                .insert(TestStruct(Point::new(&[i, 0]), "data".to_string()))
-                .unwrap();
-        }
+        quadtree.insert(Point::new(&[8, 8])).unwrap();
+        assert!(!quadtree.is_leaf());
+        assert!(quadtree.memory_footprint() > before + 4 * size_of::<QuadTree<2, Point<2>>>());
+    }
 
-        assert_eq!(quadtree.points.len(), 4);
-        assert!(quadtree.subtrees.is_none());
+    #[test]
+    fn test_quadtree_reserve() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(8).unwrap());
+        quadtree.reserve(100);
+        let capacity = quadtree.points.capacity();
+        assert!(capacity >= 100);
+
+        // Reserving doesn't change when the node subdivides
+        quadtree.extend((0..8).map(|i| Point::new(&[i, i])));
+        assert_eq!(quadtree.node_count(), 1);
+        assert_eq!(quadtree.points.capacity(), capacity);
+        quadtree.insert(Point::new(&[9, 9])).unwrap();
+        assert!(quadtree.node_count() > 1);
     }
 
     #[test]
-    fn test_quadtree_insert_above_capacity() {
+    fn test_quadtree_centroid() {
         let region = Region::new(&[
             Interval::try_new(0.0, 10.0).unwrap(),
             Interval::try_new(0.0, 10.0).unwrap(),
         ]);
-        let mut quadtree = QuadTree::new(&region, NonZero::new(4).unwrap());
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        quadtree.extend([
+            Point::new(&[2, 2]),
+            Point::new(&[6, 2]),
+            Point::new(&[2, 6]),
+            Point::new(&[6, 6]),
+            Point::new(&[9, 9]),
+        ]);
 
-        for i in 0..4 {
-            quadtree
-                .insert(TestStruct(Point::new(&[i, 0]), "data".to_string()))
-                .unwrap();
-        }
+        let square = Region::new(&[
+            Interval::try_new(0.0, 8.0).unwrap(),
+            Interval::try_new(0.0, 8.0).unwrap(),
+        ]);
+        assert_eq!(quadtree.centroid(&square), Some(Point::new(&[4, 4])));
 
-        assert_eq!(quadtree.points.len(), 4);
-        assert!(quadtree.subtrees.is_none());
+        let empty = Region::new(&[
+            Interval::try_new(7.0, 8.0).unwrap(),
+            Interval::try_new(0.0, 1.0).unwrap(),
+        ]);
+        assert_eq!(quadtree.centroid(&empty), None);
+    }
 
-        // Insert one more point to trigger subdivision
-        quadtree
-            .insert(TestStruct(
-                Point::new(&[5, 5]),
-                "data_subdivided".to_string(),
-            ))
-            .unwrap();
+    #[test]
+    fn test_quadtree_query_owned() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        quadtree.extend((0..10).map(|i| Point::new(&[i, i])));
 
-        // Check that the quadtree has subdivided
-        assert!(quadtree.subtrees.is_some());
-        let subtrees = quadtree.subtrees.as_ref().unwrap();
-        assert_eq!(subtrees.len(), 4);
+        let results = quadtree.query_owned(Point::new(&[5.0, 5.0]).to_distance_based_query(1.5));
+        assert_eq!(
+            results.into_iter().copied().sorted().collect_vec(),
+            vec![
+                Point::new(&[4, 4]),
+                Point::new(&[5, 5]),
+                Point::new(&[6, 6])
+            ]
+        );
+    }
 
-        // Assert the point went into only 1 subtree
-        let subtree_total_points: usize = subtrees.iter().map(|st| st.points.len()).sum();
-        assert_eq!(subtree_total_points, 1);
-        assert!(
-            subtrees
-                .iter()
-                .flat_map(|subtree| subtree.points.iter())
-                .all(|p| p.item().1 == "data_subdivided")
+    #[test]
+    fn test_quadtree_query_range() -> Result<()> {
+        let region = Region::new(&[Interval::try_new(0.0, 10.0)?, Interval::try_new(0.0, 10.0)?]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        quadtree.extend(
+            (0..10)
+                .cartesian_product(0..10)
+                .map(|(x, y)| Point::new(&[x, y])),
         );
 
-        assert!(quadtree.regions().len() > 1);
+        let manual = Region::new(&[Interval::try_new(2.0, 5.0)?, Interval::try_new(3.0, 4.5)?]);
+        let expected = quadtree.query(&manual).sorted().collect_vec();
+        let results = quadtree.query_range(&[2.0, 3.0], &[5.0, 4.5])?;
+        assert_eq!(results.into_iter().sorted().collect_vec(), expected);
+        assert_eq!(expected.len(), 6);
+
+        // Each axis must have min below max
+        assert!(quadtree.query_range(&[2.0, 3.0], &[5.0, 3.0]).is_err());
+        assert!(quadtree.query_range(&[6.0, 3.0], &[5.0, 4.0]).is_err());
+        Ok(())
     }
 
     #[test]
-    fn test_quadtree_query() {
+    fn test_quadtree_extend() {
         let region = Region::new(&[
             Interval::try_new(0.0, 10.0).unwrap(),
             Interval::try_new(0.0, 10.0).unwrap(),
         ]);
-        let mut quadtree = QuadTree::new(&region, NonZero::new(4).unwrap());
-
-        for i in 0..4 {
-            quadtree
-                .insert(TestStruct(Point::new(&[i, 0]), "data".to_string()))
-                .unwrap();
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        quadtree.extend((0..10).map(|i| Point::new(&[i, i])));
+        assert_eq!(quadtree.query(&region).count(), 10);
+        for i in 0..10 {
+            assert!(quadtree.contains_point(&Point::new(&[i, i])));
         }
 
-        // Construct query region that should only contain only the first two points
-        let query_region = Region::new(&[
-            Interval::try_new(0.0, 2.0).unwrap(),
+        // try_extend stops at the first out of bounds item
+        let result = quadtree.try_extend([
+            Point::new(&[1.5, 1.5]),
+            Point::new(&[20.0, 20.0]),
+            Point::new(&[2.5, 2.5]),
+        ]);
+        assert!(result.is_err());
+        assert_eq!(quadtree.query(&region).count(), 11);
+    }
+
+    #[test]
+    fn test_quadtree_tuples() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
             Interval::try_new(0.0, 10.0).unwrap(),
         ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        for (x, y, name) in [(1, 1, "a"), (2, 8, "b"), (5, 5, "c"), (9, 9, "d")] {
+            quadtree.insert((Point::new(&[x, y]), name)).unwrap();
+        }
 
-        let results: Vec<_> = quadtree.query(&query_region).collect();
+        let query = DistanceQuery::new(&Point::new(&[3.0, 3.0]), 3.0);
+        let names: Vec<&str> = quadtree
+            .query(&query)
+            .map(|(_, name)| *name)
+            .sorted()
+            .collect();
+        assert_eq!(names, vec!["a", "c"]);
+    }
 
-        assert_eq!(results.len(), 2);
+    #[test]
+    fn test_quadtree_insert_quantized() -> Result<()> {
+        let region = Region::new(&[Interval::try_new(0.0, 10.0)?, Interval::try_new(0.0, 10.0)?]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        let mut insert = |x: f64, y: f64| {
+            quadtree.insert_quantized(
+                &Point::new(&[x, y]),
+                0.5,
+                |snapped| (snapped, 1),
+                |(_, count)| *count += 1,
+            )
+        };
+
+        // Both within the cell around (2.5, 2.5)
+        insert(2.4, 2.6)?;
+        insert(2.6, 2.4)?;
+        insert(7.1, 7.0)?;
+        assert!(insert(3.0, 3.0).is_ok());
+        assert!(insert(f64::NAN, 3.0).is_err());
+        let mut stored = quadtree.query(&region).copied().collect_vec();
+        stored.sort();
+        assert_eq!(
+            stored,
+            vec![
+                (Point::new(&[2.5, 2.5]), 2),
+                (Point::new(&[3.0, 3.0]), 1),
+                (Point::new(&[7.0, 7.0]), 1)
+            ]
+        );
+
+        assert!(
+            quadtree
+                .insert_quantized(&Point::new(&[1.0, 1.0]), 0.0, |p| (p, 1), |_| ())
+                .is_err()
+        );
+        Ok(())
     }
 
     #[test]
-    fn test_quadtree_query_subdivided() {
+    fn test_quadtree_entry() {
         let region = Region::new(&[
             Interval::try_new(0.0, 10.0).unwrap(),
             Interval::try_new(0.0, 10.0).unwrap(),
         ]);
-        // Capacity of 2 will ensure lots of subdivision when inserting 10 items
         let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
-
-        for i in 0..10 {
+        for i in 0..10u32 {
             quadtree
-                .insert(TestStruct(Point::new(&[i, 0]), "data".to_string()))
+                .insert(Entry::new(Point::new(&[i, i]), i * 10))
                 .unwrap();
         }
 
-        // Construct query region
-        let query_region = Region::new(&[
-            Interval::try_new(0.0, 10.0).unwrap(),
-            Interval::try_new(0.0, 10.0).unwrap(),
+        let query = Region::new(&[
+            Interval::try_new(2.0, 5.0).unwrap(),
+            Interval::try_new(2.0, 5.0).unwrap(),
         ]);
-
-        let results: Vec<_> = quadtree.query(&query_region).collect();
-
-        assert_eq!(results.len(), 10);
+        let values: Vec<u32> = quadtree
+            .query(&query)
+            .map(|entry| *entry.value())
+            .sorted()
+            .collect();
+        assert_eq!(values, vec![20, 30, 40]);
     }
 
     #[test]
-    fn test_quadtree_many_points() {
+    fn test_quadtree_nearest_iter() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(7);
         let region = Region::new(&[
             Interval::try_new(0.0, 100.0).unwrap(),
             Interval::try_new(0.0, 100.0).unwrap(),
         ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(4).unwrap());
+        let points: Vec<Point<2>> = (0..200).map(|_| region.sample_point(&mut rng)).collect();
+        for point in &points {
+            quadtree.insert(*point).unwrap();
+        }
 
-        // Capacity of 100 will ensure lots of subdivision when inserting 100,000 items
-        let mut quadtree = QuadTree::new(&region, NonZero::new(100).unwrap());
-        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
+        let target = Point::new(&[42.0, 17.0]);
+        let first_three: Vec<_> = quadtree.nearest_iter(&target).take(3).collect();
+        assert_eq!(first_three, quadtree.nearest(&target, 3));
 
-        for _ in 0..100_000 {
-            // Choose random x and y coordinates
-            let x = rng.random_range(0..100);
-            let y = rng.random_range(0..100);
-            quadtree
-                .insert(TestStruct(Point::new(&[x, y]), "data".to_string()))
-                .unwrap();
-        }
+        // Lazily yielded distances match a full sort of the dataset
+        let yielded: Vec<f64> = quadtree
+            .nearest_iter(&target)
+            .map(|point| point.distance(&target))
+            .collect();
+        let expected: Vec<f64> = points
+            .iter()
+            .map(|point| point.distance(&target))
+            .sorted_by(f64::total_cmp)
+            .collect();
+        assert_eq!(yielded, expected);
+    }
 
-        // Construct query region
-        let query_region = Region::new(&[
-            Interval::try_new(0.0, 10.0).unwrap(),
-            Interval::try_new(0.0, 10.0).unwrap(),
-        ]);
+    /// Builds and queries the same grid of points with any scalar type
+    fn scalar_tree_matches<F: Scalar>() -> (usize, usize, Option<Point<2, F>>) {
+        let axis = Interval::try_new(F::from_f64(0.0), F::from_f64(10.0)).unwrap();
+        let region = Region::new(&[axis, axis]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        for x in 0..10 {
+            for y in 0..10 {
+                quadtree
+                    .insert(Point::from([F::from_f64(x as f64), F::from_f64(y as f64)]))
+                    .unwrap();
+            }
+        }
 
-        let results: Vec<_> = quadtree.query(&query_region).collect();
-        dbg!(&results.len());
-        assert!((900..=1100).contains(&results.len()));
+        let half = Interval::try_new(F::from_f64(0.0), F::from_f64(5.0)).unwrap();
+        let in_region = quadtree.query(&Region::new(&[half, half])).count();
+        let center = Point::from([F::from_f64(5.0), F::from_f64(5.0)]);
+        let in_distance = quadtree
+            .query(&center.to_distance_based_query(F::from_f64(1.5)))
+            .count();
+        (
+            in_region,
+            in_distance,
+            quadtree.nearest_one(&center).copied(),
+        )
     }
 
     #[test]
-    fn test_quadtree_single_point_interval() {
-        const COUNT: usize = 10;
-        // Create a quadtree where the region is a single point
-        let region = Region::new(&[
-            Interval::try_new(1.0, 1.0 + f64::EPSILON).unwrap(),
-            Interval::try_new(1.0, 1.0 + f64::EPSILON).unwrap(),
-        ]);
-        let mut quadtree = QuadTree::new(&region, NonZero::new(1).unwrap());
+    fn test_quadtree_f32_and_f64() {
+        let (in_region, in_distance, nearest) = scalar_tree_matches::<f32>();
+        assert_eq!(in_region, 25);
+        assert_eq!(in_distance, 9);
+        assert_eq!(nearest, Some(Point::from([5.0f32, 5.0])));
 
-        // Insert 10 points into the quadtree
-        for i in 0..COUNT {
-            quadtree
-                .insert(TestStruct(Point::new(&[1.0, 1.0]), format!("P{}", i)))
-                .unwrap();
-        }
+        let (in_region, in_distance, nearest) = scalar_tree_matches::<f64>();
+        assert_eq!(in_region, 25);
+        assert_eq!(in_distance, 9);
+        assert_eq!(nearest, Some(Point::new(&[5.0, 5.0])));
 
-        // Query the quadtree
-        let results: Vec<_> = quadtree.query(&region).collect();
-        assert_eq!(results.len(), 10);
-        // Assert there are 10 unique strings
-        let unique_results: Vec<_> = results.iter().map(|item| item.item().1.clone()).collect();
-        assert_eq!(
-            unique_results
-                .into_iter()
-                .unique()
-                .collect::<Vec<_>>()
-                .len(),
-            COUNT
-        );
+        // Half the memory per stored point
+        assert_eq!(size_of::<Point<2, f32>>() * 2, size_of::<Point<2>>());
     }
 
     #[test]
@@ -466,12 +3028,110 @@ mod tests {
         let count_quadtree = quadtree.query(&search_region).count();
         let elapsed_quadtree = start.elapsed();
 
+        // And again, pushing into a reusable buffer
+        let mut buffer = Vec::new();
+        let start = std::time::Instant::now();
+        quadtree.query_into(&search_region, &mut buffer);
+        let elapsed_quadtree_into = start.elapsed();
+
         dbg!(elapsed_non_quadtree);
         dbg!(elapsed_quadtree);
+        dbg!(elapsed_quadtree_into);
         dbg!(count_quadtree);
 
         // Quad tree should be faster than non-quadtree but find the same number of points
         assert_eq!(count_quadtree, count_non_quadtree);
+        assert_eq!(buffer.len(), count_non_quadtree);
         assert!(elapsed_quadtree < elapsed_non_quadtree);
     }
+
+    /// Every query and aggregate on a tree with nothing in it returns an empty result
+    mod empty_tree {
+        use super::*;
+
+        fn empty() -> (Region<2>, QuadTree<2, Point<2>>) {
+            let region = Region::new(&[
+                Interval::try_new(0.0, 10.0).unwrap(),
+                Interval::try_new(0.0, 10.0).unwrap(),
+            ]);
+            let quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+            (region, quadtree)
+        }
+
+        #[test]
+        fn test_empty_queries() {
+            let (region, quadtree) = empty();
+            let distance = Point::new(&[5.0, 5.0]).to_distance_based_query(3.0);
+            assert_eq!(quadtree.query(&region).count(), 0);
+            assert_eq!(quadtree.query(&distance).count(), 0);
+            assert!(quadtree.query_owned(region.clone()).is_empty());
+            assert!(quadtree.query_cloned(&region).is_empty());
+            assert!(quadtree.query_sorted(&region).is_empty());
+            assert_eq!(quadtree.query_with_points(&region).count(), 0);
+            assert!(
+                quadtree
+                    .query_range(&[0.0, 0.0], &[5.0, 5.0])
+                    .unwrap()
+                    .is_empty()
+            );
+            assert!(
+                quadtree
+                    .query_by_distance(&Point::new(&[5.0, 5.0]), 3.0)
+                    .is_empty()
+            );
+            assert_eq!(quadtree.query_partition(&region, &region), (vec![], vec![]));
+
+            let mut into = Vec::new();
+            quadtree.query_into(&region, &mut into);
+            assert!(into.is_empty());
+
+            let mut quadtree = quadtree;
+            assert_eq!(quadtree.query_mut(&region).count(), 0);
+        }
+
+        #[cfg(feature = "rayon")]
+        #[test]
+        fn test_empty_par_query() {
+            let (region, quadtree) = empty();
+            assert!(quadtree.par_query(&region).is_empty());
+        }
+
+        #[test]
+        fn test_empty_aggregates() {
+            let (region, quadtree) = empty();
+            assert_eq!(quadtree.count(&region), 0);
+            assert_eq!(quadtree.centroid(&region), None);
+            assert_eq!(
+                quadtree
+                    .query_heatmap(&region, NonZero::new(2).unwrap())
+                    .unwrap(),
+                vec![0; 4]
+            );
+        }
+
+        #[test]
+        fn test_empty_nearest() {
+            let (_, quadtree) = empty();
+            let target = Point::new(&[5.0, 5.0]);
+            assert_eq!(quadtree.nearest_one(&target), None);
+            assert!(quadtree.nearest(&target, 3).is_empty());
+            assert_eq!(quadtree.nearest_iter(&target).count(), 0);
+            assert!(!quadtree.contains_point(&target));
+        }
+
+        #[test]
+        fn test_empty_structure() {
+            let (region, mut quadtree) = empty();
+            assert_eq!(quadtree.depth(), 1);
+            assert_eq!(quadtree.node_count(), 1);
+            assert!(quadtree.is_leaf());
+            assert!(quadtree.children().is_none());
+            assert_eq!(quadtree.leaves().len(), 1);
+            assert_eq!(quadtree.leaf_densities(), vec![(region.clone(), 0)]);
+            assert_eq!(quadtree.leaf_cells_touched(&region), 1);
+            assert_eq!(quadtree.repair(), 0);
+            quadtree.retain(|_| false);
+            assert_eq!(quadtree.clone_structure_empty(), quadtree);
+        }
+    }
 }
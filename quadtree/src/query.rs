@@ -1,6 +1,9 @@
+use std::marker::PhantomData;
+
+use eyre::{Result, ensure};
 use itertools::Itertools;
 
-use crate::{interval::Interval, point::Point, region::Region};
+use crate::{interval::Interval, point::Point, region::Region, scalar::Scalar};
 
 /// [Query] is trait that allows for querying a region in n-dimensional space.
 /// This is trivial for the existing [Region] struct, but can be extended for other types of queries.
@@ -8,43 +11,746 @@ use crate::{interval::Interval, point::Point, region::Region};
 /// For example, [DistanceQuery] is implemented by creating a new struct that implements this trait.
 /// The region should be the bounding box of the 'circle' (or sphere, or n-dimensional shape) and the
 /// `contains` method would check if the point is within the circle.
-pub trait Query<const N: usize> {
-    fn region(&self) -> &Region<N>;
-    fn contains(&self, point: &Point<N>) -> bool;
+///
+/// Containment differs between the provided queries:
+/// - [Region] is half-open, a point on the upper bound of any axis is excluded
+/// - [InclusiveRegion] is closed, points on any boundary are included
+/// - [DistanceQuery] is closed, points exactly `radius` away are included
+/// - [SurfaceQuery] is closed, points exactly `tolerance` from the surface are included
+/// - [MetricQuery] is closed, points exactly `radius` away under its metric are included
+/// - [EllipsoidQuery] is closed, points on the ellipsoid surface are included
+/// - [SegmentQuery] is closed, points exactly `radius` from the segment are included
+/// - [PolygonQuery] is unspecified on the polygon's edges, which may go either way
+/// - [AndQuery] and [OrQuery] inherit the containment of the queries they combine
+/// - `GeoPolygonQuery` (with the `geo` feature) is open, points on the polygon's boundary are excluded
+/// - [PointQuery] selects points exactly equal to its point
+/// - [NotQuery] is half-open on its bounds, like [Region], and excludes whatever the inner query includes
+///
+/// Queries are over [f64] coordinates unless another [Scalar] is given, e.g. `Query<2, f32>`.
+pub trait Query<const N: usize, F: Scalar = f64> {
+    fn region(&self) -> &Region<N, F>;
+    fn contains(&self, point: &Point<N, F>) -> bool;
+
+    /// Whether every point in [Query::region] (half-open) is contained by the query,
+    /// so nodes nested inside it can be taken whole. False unless the query says otherwise.
+    fn region_implies_contains(&self) -> bool {
+        false
+    }
+
+    /// How a (node) region relates to this query, used to prune the search.
+    /// The default only knows about the bounding region, so it only reports [Containment::Inside]
+    /// when [Query::region_implies_contains] holds.
+    fn region_relationship(&self, region: &Region<N, F>) -> Containment {
+        // The node is taken as closed, as nodes on the tree's upper edge hold points on it
+        if !region.intersects_inclusive(self.region()) {
+            return Containment::Outside;
+        }
+        let nested = self
+            .region()
+            .intervals()
+            .iter()
+            .zip(region.intervals().iter())
+            // Strictly below the upper bound, as a node can hold points on its own upper edge
+            .all(|(outer, inner)| outer.start() <= inner.start() && inner.end() < outer.end());
+        if nested && self.region_implies_contains() {
+            Containment::Inside
+        } else {
+            Containment::Partial
+        }
+    }
+}
+
+/// The relationship between a region and a [Query]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Containment {
+    /// Every point in the region is contained by the query
+    Inside,
+    /// No point in the region is contained by the query
+    Outside,
+    /// Some points in the region may be contained by the query
+    Partial,
+}
+
+#[derive(Clone, Debug)]
+pub struct DistanceQuery<const N: usize, F = f64> {
+    center: Point<N, F>,
+    radius: F,
+    /// Precomputed so containment checks can skip the `sqrt`
+    radius_squared: F,
+    region: Region<N, F>,
+}
+
+impl<const N: usize, F: Scalar> DistanceQuery<N, F> {
+    /// Panics if `radius` is negative or not finite, see [DistanceQuery::try_new]
+    pub fn new(center: &Point<N, F>, radius: F) -> Self {
+        Self::try_new(center, radius).expect("valid radius")
+    }
+
+    /// Errors if `radius` is negative or not finite.
+    /// A radius of zero selects only points equal to `center`, like [PointQuery].
+    pub fn try_new(center: &Point<N, F>, radius: F) -> Result<Self> {
+        ensure!(radius.is_finite(), "Radius must be finite");
+        ensure!(radius >= F::zero(), "Radius must not be negative");
+        let intervals = center
+            .dimension_values()
+            .iter()
+            .map(|&c| {
+                // A zero (or vanishingly small) radius still needs a non-empty bounding box
+                if c - radius < c + radius {
+                    Interval::try_new(c - radius, c + radius)
+                } else {
+                    Interval::containing_only(c)
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(DistanceQuery {
+            center: *center,
+            radius,
+            radius_squared: radius * radius,
+            region: Region::try_new(&intervals)?,
+        })
+    }
+
+    pub fn center(&self) -> &Point<N, F> {
+        &self.center
+    }
+
+    pub fn radius(&self) -> F {
+        self.radius
+    }
+}
+
+impl<const N: usize, F: Scalar> Query<N, F> for DistanceQuery<N, F> {
+    fn region(&self) -> &Region<N, F> {
+        &self.region
+    }
+
+    fn contains(&self, point: &Point<N, F>) -> bool {
+        self.center.distance_squared(point) <= self.radius_squared
+    }
+
+    fn region_relationship(&self, region: &Region<N, F>) -> Containment {
+        let farthest_squared = region
+            .intervals()
+            .iter()
+            .zip(self.center.dimension_values())
+            .map(|(interval, &c)| {
+                (c - *interval.start())
+                    .abs()
+                    .max((c - *interval.end()).abs())
+            })
+            .map(|distance| distance * distance)
+            .sum::<F>();
+
+        // The sphere is convex, so if the farthest corner is inside, the whole region is
+        if farthest_squared <= self.radius_squared {
+            Containment::Inside
+        } else if region.min_distance(&self.center) > self.radius {
+            Containment::Outside
+        } else {
+            Containment::Partial
+        }
+    }
+}
+
+/// A query for points on the surface of a sphere, within `tolerance` of exactly `radius`
+/// from `center`. Unlike [DistanceQuery] the inside is hollow. Containment is closed.
+#[derive(Clone, Debug)]
+pub struct SurfaceQuery<const N: usize, F = f64> {
+    /// The solid sphere out to `radius + tolerance`, which bounds the surface
+    outer: DistanceQuery<N, F>,
+    radius: F,
+    tolerance: F,
+}
+
+impl<const N: usize, F: Scalar> SurfaceQuery<N, F> {
+    /// Panics if `radius` or `tolerance` is negative or not finite, see [SurfaceQuery::try_new]
+    pub fn new(center: &Point<N, F>, radius: F, tolerance: F) -> Self {
+        Self::try_new(center, radius, tolerance).expect("valid radius and tolerance")
+    }
+
+    /// Errors if `radius` or `tolerance` is negative or not finite
+    pub fn try_new(center: &Point<N, F>, radius: F, tolerance: F) -> Result<Self> {
+        ensure!(
+            tolerance.is_finite() && tolerance >= F::zero(),
+            "Tolerance must be finite and not negative"
+        );
+        ensure!(
+            radius.is_finite() && radius >= F::zero(),
+            "Radius must be finite and not negative"
+        );
+        Ok(SurfaceQuery {
+            outer: DistanceQuery::try_new(center, radius + tolerance)?,
+            radius,
+            tolerance,
+        })
+    }
+
+    pub fn center(&self) -> &Point<N, F> {
+        self.outer.center()
+    }
+
+    pub fn radius(&self) -> F {
+        self.radius
+    }
+
+    pub fn tolerance(&self) -> F {
+        self.tolerance
+    }
+}
+
+impl<const N: usize, F: Scalar> Query<N, F> for SurfaceQuery<N, F> {
+    fn region(&self) -> &Region<N, F> {
+        self.outer.region()
+    }
+
+    fn contains(&self, point: &Point<N, F>) -> bool {
+        (self.center().distance(point) - self.radius).abs() <= self.tolerance
+    }
+
+    fn region_relationship(&self, region: &Region<N, F>) -> Containment {
+        if self.outer.region_relationship(region) == Containment::Outside {
+            return Containment::Outside;
+        }
+        let farthest = region
+            .intervals()
+            .iter()
+            .zip(self.center().dimension_values())
+            .map(|(interval, &c)| {
+                (c - *interval.start())
+                    .abs()
+                    .max((c - *interval.end()).abs())
+            })
+            .map(|distance| distance * distance)
+            .sum::<F>()
+            .sqrt();
+
+        // A region entirely within the hollow inside can't reach the surface
+        if farthest < self.radius - self.tolerance {
+            Containment::Outside
+        } else {
+            Containment::Partial
+        }
+    }
+}
+
+/// A distance function between points, used by [MetricQuery]
+pub trait Metric<const N: usize> {
+    fn distance(a: &Point<N>, b: &Point<N>) -> f64;
+
+    /// The bounding region of all points within `radius` of `center`, for a radius that is
+    /// finite and not negative.
+    /// Defaults to the closed box `[c - radius, c + radius]` on every axis, which covers the balls
+    /// of the metrics provided here. A radius of 0 gives the narrowest box around the center,
+    /// see [Interval::containing_only].
+    fn bounding_region(center: &Point<N>, radius: f64) -> Region<N> {
+        let intervals = center
+            .dimension_values()
+            .iter()
+            .map(|&c| {
+                Interval::try_new(c - radius, (c + radius).next_up())
+                    .expect("radius is not negative")
+            })
+            .collect_array()
+            .expect("same sized array");
+        Region::new(&intervals)
+    }
+}
+
+/// Straight line distance
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Euclidean;
+
+/// Sum of the absolute differences along each axis
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Manhattan;
+
+/// Largest absolute difference along any axis
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Chebyshev;
+
+impl<const N: usize> Metric<N> for Euclidean {
+    fn distance(a: &Point<N>, b: &Point<N>) -> f64 {
+        a.distance(b)
+    }
+}
+
+impl<const N: usize> Metric<N> for Manhattan {
+    fn distance(a: &Point<N>, b: &Point<N>) -> f64 {
+        a.dimension_values()
+            .iter()
+            .zip(b.dimension_values())
+            .map(|(a, b)| (a - b).abs())
+            .sum()
+    }
+}
+
+impl<const N: usize> Metric<N> for Chebyshev {
+    fn distance(a: &Point<N>, b: &Point<N>) -> f64 {
+        a.dimension_values()
+            .iter()
+            .zip(b.dimension_values())
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f64::max)
+    }
 }
 
+/// A query for all points within `radius` of `center` under the [Metric] `M`.
+/// Containment is closed. `MetricQuery<N, Euclidean>` behaves like [DistanceQuery].
 #[derive(Clone, Debug)]
-pub struct DistanceQuery<const N: usize> {
+pub struct MetricQuery<const N: usize, M> {
     center: Point<N>,
     radius: f64,
     region: Region<N>,
+    metric: PhantomData<M>,
 }
 
-impl<const N: usize> DistanceQuery<N> {
+impl<const N: usize, M: Metric<N>> MetricQuery<N, M> {
+    /// Panics if `radius` is negative or not finite, see [MetricQuery::try_new]
     pub fn new(center: &Point<N>, radius: f64) -> Self {
-        let center_f64 = center.dimension_values();
-        let intervals = center_f64
-            .iter()
-            .map(|&c| Interval::try_new(c - radius, c + radius).unwrap())
-            .collect_array()
-            .expect("same sized array");
-        let region = Region::new(&intervals);
-        DistanceQuery {
+        Self::try_new(center, radius).expect("valid radius")
+    }
+
+    /// Errors if `radius` is negative or not finite.
+    /// A radius of zero selects only points equal to `center`, like [PointQuery].
+    pub fn try_new(center: &Point<N>, radius: f64) -> Result<Self> {
+        ensure!(radius.is_finite(), "Radius must be finite");
+        ensure!(radius >= 0.0, "Radius must not be negative");
+        Ok(MetricQuery {
             center: *center,
             radius,
+            region: M::bounding_region(center, radius),
+            metric: PhantomData,
+        })
+    }
+}
+
+impl<const N: usize, M: Metric<N>> Query<N> for MetricQuery<N, M> {
+    fn region(&self) -> &Region<N> {
+        &self.region
+    }
+
+    fn contains(&self, point: &Point<N>) -> bool {
+        M::distance(&self.center, point) <= self.radius
+    }
+}
+
+/// An axis-aligned ellipsoid query, like [DistanceQuery] but with a radius per axis.
+/// Useful when the axes have different scales. Containment is closed.
+#[derive(Clone, Debug)]
+pub struct EllipsoidQuery<const N: usize> {
+    center: Point<N>,
+    radii: [f64; N],
+    region: Region<N>,
+}
+
+impl<const N: usize> EllipsoidQuery<N> {
+    pub fn try_new(center: &Point<N>, radii: [f64; N]) -> Result<Self> {
+        ensure!(
+            radii.iter().all(|r| r.is_finite() && *r > 0.0),
+            "All radii must be positive and finite"
+        );
+        let intervals = center
+            .dimension_values()
+            .iter()
+            .zip(radii.iter())
+            // Closed, like containment, so points on the ellipsoid's extremes are reached
+            .map(|(&c, &r)| Interval::try_new(c - r, (c + r).next_up()))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(EllipsoidQuery {
+            center: *center,
+            radii,
+            region: Region::try_new(&intervals)?,
+        })
+    }
+}
+
+impl<const N: usize> Query<N> for EllipsoidQuery<N> {
+    fn region(&self) -> &Region<N> {
+        &self.region
+    }
+
+    fn contains(&self, point: &Point<N>) -> bool {
+        point
+            .dimension_values()
+            .iter()
+            .zip(self.center.dimension_values())
+            .zip(self.radii.iter())
+            .map(|((p, c), r)| ((p - c) / r).powi(2))
+            .sum::<f64>()
+            <= 1.0
+    }
+}
+
+/// A query selecting points inside a simple (non self-intersecting) 2D polygon.
+/// Containment uses ray casting, so points exactly on an edge may or may not be included.
+#[derive(Clone, Debug)]
+pub struct PolygonQuery {
+    vertices: Vec<Point<2>>,
+    region: Region<2>,
+}
+
+impl PolygonQuery {
+    /// Create a polygon from its vertices in order. The polygon is closed automatically,
+    /// so repeating the first vertex at the end is optional.
+    pub fn try_new(mut vertices: Vec<Point<2>>) -> Result<Self> {
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        ensure!(
+            vertices.len() >= 3,
+            "A polygon needs at least 3 vertices, got {}",
+            vertices.len()
+        );
+        let region = Region::bounding(&vertices)?;
+        Ok(PolygonQuery { vertices, region })
+    }
+
+    pub fn vertices(&self) -> &[Point<2>] {
+        &self.vertices
+    }
+}
+
+impl Query<2> for PolygonQuery {
+    fn region(&self) -> &Region<2> {
+        &self.region
+    }
+
+    fn contains(&self, point: &Point<2>) -> bool {
+        let [x, y] = *point.dimension_values();
+        // Count crossings of a ray cast from the point towards +x, odd means inside
+        let previous = self.vertices.iter().cycle().skip(self.vertices.len() - 1);
+        self.vertices
+            .iter()
+            .zip(previous)
+            .filter(|(a, b)| {
+                let [ax, ay] = *a.dimension_values();
+                let [bx, by] = *b.dimension_values();
+                (ay > y) != (by > y) && x < (bx - ax) * (y - ay) / (by - ay) + ax
+            })
+            .count()
+            % 2
+            == 1
+    }
+}
+
+/// A capsule shaped 2D query selecting points within `radius` of the line segment
+/// between two endpoints. Containment is closed.
+#[derive(Clone, Debug)]
+pub struct SegmentQuery {
+    start: Point<2>,
+    end: Point<2>,
+    radius_squared: f64,
+    region: Region<2>,
+}
+
+impl SegmentQuery {
+    pub fn try_new(start: &Point<2>, end: &Point<2>, radius: f64) -> Result<Self> {
+        ensure!(
+            radius.is_finite() && radius > 0.0,
+            "Radius must be positive and finite"
+        );
+        let region = Region::bounding(&[*start, *end])?.expand(radius)?;
+        Ok(SegmentQuery {
+            start: *start,
+            end: *end,
+            radius_squared: radius * radius,
             region,
+        })
+    }
+
+    /// The point on the segment closest to `point`
+    fn closest_point(&self, point: &Point<2>) -> Point<2> {
+        let direction = self.end - self.start;
+        let length_squared = direction.distance_squared(&Point::default());
+        if length_squared == 0.0 {
+            return self.start;
+        }
+        let offset = *point - self.start;
+        let [dx, dy] = *direction.dimension_values();
+        let [ox, oy] = *offset.dimension_values();
+        let t = ((dx * ox + dy * oy) / length_squared).clamp(0.0, 1.0);
+        self.start.lerp(&self.end, t)
+    }
+}
+
+impl Query<2> for SegmentQuery {
+    fn region(&self) -> &Region<2> {
+        &self.region
+    }
+
+    fn contains(&self, point: &Point<2>) -> bool {
+        self.closest_point(point).distance_squared(point) <= self.radius_squared
+    }
+}
+
+/// A query selecting points inside a [geo::Polygon], e.g. one loaded from GeoJSON.
+/// Containment follows [geo::Contains], so points on the boundary are excluded.
+#[cfg(feature = "geo")]
+#[derive(Clone, Debug)]
+pub struct GeoPolygonQuery {
+    polygon: geo::Polygon<f64>,
+    region: Region<2>,
+}
+
+#[cfg(feature = "geo")]
+impl GeoPolygonQuery {
+    /// Errors if the polygon is empty or has no area to bound
+    pub fn try_new(polygon: geo::Polygon<f64>) -> Result<Self> {
+        use geo::BoundingRect;
+        let rect = polygon
+            .bounding_rect()
+            .ok_or_else(|| eyre::eyre!("Polygon has no bounding rect"))?;
+        Ok(GeoPolygonQuery {
+            region: Region::try_from(rect)?,
+            polygon,
+        })
+    }
+}
+
+#[cfg(feature = "geo")]
+impl Query<2> for GeoPolygonQuery {
+    fn region(&self) -> &Region<2> {
+        &self.region
+    }
+
+    fn contains(&self, point: &Point<2>) -> bool {
+        use geo::Contains;
+        self.polygon.contains(&geo::Point::from(*point))
+    }
+}
+
+/// Selects points contained by both queries.
+#[derive(Clone, Debug)]
+pub struct AndQuery<const N: usize, A, B, F = f64> {
+    a: A,
+    b: B,
+    region: Region<N, F>,
+}
+
+impl<const N: usize, F: Scalar, A: Query<N, F>, B: Query<N, F>> AndQuery<N, A, B, F> {
+    /// The region is the intersection of both regions. If they don't overlap nothing can
+    /// match, and the first query's region is kept as a placeholder.
+    pub fn new(a: A, b: B) -> Self {
+        let region = a
+            .region()
+            .intersection(b.region())
+            .unwrap_or_else(|| a.region().clone());
+        AndQuery { a, b, region }
+    }
+}
+
+impl<const N: usize, F: Scalar, A: Query<N, F>, B: Query<N, F>> Query<N, F>
+    for AndQuery<N, A, B, F>
+{
+    fn region(&self) -> &Region<N, F> {
+        &self.region
+    }
+
+    fn contains(&self, point: &Point<N, F>) -> bool {
+        self.a.contains(point) && self.b.contains(point)
+    }
+
+    fn region_relationship(&self, region: &Region<N, F>) -> Containment {
+        match (
+            self.a.region_relationship(region),
+            self.b.region_relationship(region),
+        ) {
+            (Containment::Outside, _) | (_, Containment::Outside) => Containment::Outside,
+            (Containment::Inside, Containment::Inside) => Containment::Inside,
+            _ => Containment::Partial,
+        }
+    }
+}
+
+/// Selects points contained by either query.
+#[derive(Clone, Debug)]
+pub struct OrQuery<const N: usize, A, B, F = f64> {
+    a: A,
+    b: B,
+    region: Region<N, F>,
+}
+
+impl<const N: usize, F: Scalar, A: Query<N, F>, B: Query<N, F>> OrQuery<N, A, B, F> {
+    pub fn new(a: A, b: B) -> Self {
+        let region = a.region().merge(b.region());
+        OrQuery { a, b, region }
+    }
+}
+
+impl<const N: usize, F: Scalar, A: Query<N, F>, B: Query<N, F>> Query<N, F>
+    for OrQuery<N, A, B, F>
+{
+    fn region(&self) -> &Region<N, F> {
+        &self.region
+    }
+
+    fn contains(&self, point: &Point<N, F>) -> bool {
+        self.a.contains(point) || self.b.contains(point)
+    }
+
+    fn region_relationship(&self, region: &Region<N, F>) -> Containment {
+        match (
+            self.a.region_relationship(region),
+            self.b.region_relationship(region),
+        ) {
+            (Containment::Inside, _) | (_, Containment::Inside) => Containment::Inside,
+            (Containment::Outside, Containment::Outside) => Containment::Outside,
+            _ => Containment::Partial,
+        }
+    }
+}
+
+/// Selects points within `bounds` that the inner query does not contain.
+/// The bounds are required as an unbounded complement couldn't prune the tree.
+#[derive(Clone, Debug)]
+pub struct NotQuery<const N: usize, Q, F = f64> {
+    inner: Q,
+    bounds: Region<N, F>,
+}
+
+impl<const N: usize, F: Scalar, Q: Query<N, F>> NotQuery<N, Q, F> {
+    pub fn new(inner: Q, bounds: Region<N, F>) -> Self {
+        NotQuery { inner, bounds }
+    }
+}
+
+impl<const N: usize, F: Scalar, Q: Query<N, F>> Query<N, F> for NotQuery<N, Q, F> {
+    fn region(&self) -> &Region<N, F> {
+        &self.bounds
+    }
+
+    fn contains(&self, point: &Point<N, F>) -> bool {
+        self.bounds.contains(point) && !self.inner.contains(point)
+    }
+
+    fn region_relationship(&self, region: &Region<N, F>) -> Containment {
+        match self.inner.region_relationship(region) {
+            Containment::Inside => Containment::Outside,
+            Containment::Outside => self.bounds.region_relationship(region),
+            Containment::Partial => match self.bounds.region_relationship(region) {
+                Containment::Outside => Containment::Outside,
+                _ => Containment::Partial,
+            },
+        }
+    }
+}
+
+/// A [Region] query whose containment is closed on both ends of every axis,
+/// so points on the upper boundary are selected too.
+#[derive(Clone, Debug)]
+pub struct InclusiveRegion<const N: usize, F = f64> {
+    selection: Region<N, F>,
+    region: Region<N, F>,
+}
+
+impl<const N: usize, F: Scalar> InclusiveRegion<N, F> {
+    pub fn new(region: &Region<N, F>) -> Self {
+        // The pruning region is nudged up by the smallest step so that subtrees starting
+        // exactly on the upper bound are still searched.
+        let intervals = region
+            .intervals()
+            .iter()
+            .map(|interval| {
+                Interval::try_new(*interval.start(), interval.end().next_up())
+                    .expect("next_up keeps start < end")
+            })
+            .collect_array()
+            .expect("same sized array");
+        InclusiveRegion {
+            selection: region.clone(),
+            region: Region::new(&intervals),
+        }
+    }
+}
+
+impl<const N: usize, F: Scalar> Query<N, F> for InclusiveRegion<N, F> {
+    fn region(&self) -> &Region<N, F> {
+        &self.region
+    }
+
+    fn contains(&self, point: &Point<N, F>) -> bool {
+        self.selection.contains_inclusive(point)
+    }
+
+    /// The nudged region holds exactly the points in the closed selection
+    fn region_implies_contains(&self) -> bool {
+        true
+    }
+}
+
+/// A membership probe selecting the items stored at exactly one point, e.g. coincident points.
+/// [Point] can't be a [Query] itself as it has no [Region] to lend, so this pairs it with
+/// the narrowest region around it, see [Interval::containing_only].
+#[derive(Clone, Debug)]
+pub struct PointQuery<const N: usize, F = f64> {
+    point: Point<N, F>,
+    region: Region<N, F>,
+}
+
+impl<const N: usize, F: Scalar> PointQuery<N, F> {
+    /// Errors if any coordinate is the largest finite value, as no interval can hold it
+    pub fn try_new(point: &Point<N, F>) -> Result<Self> {
+        let intervals = point
+            .dimension_values()
+            .iter()
+            .map(|&value| Interval::containing_only(value))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(PointQuery {
+            point: *point,
+            region: Region::try_new(&intervals)?,
+        })
+    }
+}
+
+impl<const N: usize, F: Scalar> Query<N, F> for PointQuery<N, F> {
+    fn region(&self) -> &Region<N, F> {
+        &self.region
+    }
+
+    fn contains(&self, point: &Point<N, F>) -> bool {
+        *point == self.point
+    }
+
+    /// Only nodes that could hold the point are searched, including those holding it on
+    /// their upper edge, which the half-open region alone would skip
+    fn region_relationship(&self, region: &Region<N, F>) -> Containment {
+        if region.contains_inclusive(&self.point) {
+            Containment::Partial
+        } else {
+            Containment::Outside
         }
     }
 }
 
-impl<const N: usize> Query<N> for DistanceQuery<N> {
+/// A [Query] built from a bounding [Region] and a closure, for arbitrary shapes that
+/// don't warrant their own struct. The region is used to prune the search and the
+/// closure decides containment, so it should only accept points inside the region.
+#[derive(Clone)]
+pub struct FnQuery<const N: usize, P> {
+    region: Region<N>,
+    predicate: P,
+}
+
+impl<const N: usize, P> FnQuery<N, P>
+where
+    P: Fn(&Point<N>) -> bool,
+{
+    pub fn new(region: Region<N>, predicate: P) -> Self {
+        FnQuery { region, predicate }
+    }
+}
+
+impl<const N: usize, P> Query<N> for FnQuery<N, P>
+where
+    P: Fn(&Point<N>) -> bool,
+{
     fn region(&self) -> &Region<N> {
         &self.region
     }
 
     fn contains(&self, point: &Point<N>) -> bool {
-        let distance = self.center.distance(point);
-        distance <= self.radius
+        (self.predicate)(point)
     }
 }
 
@@ -72,6 +778,638 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_inclusive_region_query() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(1).unwrap());
+        for i in 0..10 {
+            quadtree.insert(TestStruct(Point::new(&[i, i]))).unwrap();
+        }
+
+        // Point (5, 5) sits exactly on the upper bound of the query
+        let query_region = Region::new(&[
+            Interval::try_new(0.0, 5.0).unwrap(),
+            Interval::try_new(0.0, 5.0).unwrap(),
+        ]);
+        assert_eq!(quadtree.query(&query_region).count(), 5);
+
+        let inclusive = InclusiveRegion::new(&query_region);
+        assert!(inclusive.contains(&Point::new(&[5.0, 5.0])));
+        assert_eq!(quadtree.query(&inclusive).count(), 6);
+    }
+
+    #[test]
+    fn test_point_query() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(1).unwrap());
+        for point in [[3.0, 4.0], [3.0, 4.0], [3.0, 4.0], [3.0, 4.5], [10.0, 10.0]] {
+            quadtree.insert(Point::new(&point)).unwrap();
+        }
+        quadtree.extend((0..10).map(|i| Point::new(&[i, i])));
+
+        let probe = PointQuery::try_new(&Point::new(&[3.0, 4.0])).unwrap();
+        let results: Vec<_> = quadtree.query(&probe).collect();
+        assert_eq!(results, vec![&Point::new(&[3.0, 4.0]); 3]);
+
+        // Found on the tree's upper corner too
+        let corner = PointQuery::try_new(&Point::new(&[10.0, 10.0])).unwrap();
+        assert_eq!(quadtree.query(&corner).count(), 1);
+
+        let missing = PointQuery::try_new(&Point::new(&[3.0, 4.25])).unwrap();
+        assert_eq!(quadtree.query(&missing).count(), 0);
+
+        assert!(PointQuery::try_new(&Point::new(&[f64::MAX, 0.0])).is_err());
+    }
+
+    #[test]
+    fn test_fn_query() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        for x in 0..10 {
+            for y in 0..10 {
+                quadtree.insert(TestStruct(Point::new(&[x, y]))).unwrap();
+            }
+        }
+
+        // Select only points strictly above the diagonal
+        let above_diagonal = FnQuery::new(region.clone(), |point: &Point<2>| {
+            let [x, y] = point.dimension_values();
+            y > x
+        });
+        let results: Vec<_> = quadtree.query(&above_diagonal).collect();
+        assert_eq!(results.len(), 45);
+        assert!(results.iter().all(|item| {
+            let [x, y] = item.point().dimension_values();
+            y > x
+        }));
+    }
+
+    #[test]
+    fn test_metric_query() {
+        let center = Point::new(&[0.0, 0.0]);
+        let diagonal = Point::new(&[2.0, 2.0]);
+        let euclidean = MetricQuery::<2, Euclidean>::new(&center, 3.0);
+        let manhattan = MetricQuery::<2, Manhattan>::new(&center, 3.0);
+        let chebyshev = MetricQuery::<2, Chebyshev>::new(&center, 3.0);
+        assert!(euclidean.contains(&diagonal));
+        assert!(!manhattan.contains(&diagonal));
+        assert!(chebyshev.contains(&Point::new(&[3.0, 3.0])));
+        assert_eq!(euclidean.region(), chebyshev.region());
+    }
+
+    #[test]
+    fn test_metric_query_boundary_in_tree() {
+        let mut quadtree = QuadTree::new(
+            &Region::new(&[
+                Interval::try_new(0.0, 1.0).unwrap(),
+                Interval::try_new(0.0, 1.0).unwrap(),
+            ]),
+            NonZero::new(1).unwrap(),
+        );
+        let points = [
+            Point::new(&[0.5, 0.25]),
+            Point::new(&[0.1, 0.1]),
+            Point::new(&[0.9, 0.9]),
+            Point::new(&[0.2, 0.8]),
+        ];
+        for point in points {
+            quadtree.insert(point).unwrap();
+        }
+
+        // (0.5, 0.25) is exactly `radius` away, in a node starting at x = 0.5
+        let center = Point::new(&[0.25, 0.25]);
+        let chebyshev = MetricQuery::<2, Chebyshev>::new(&center, 0.25);
+        let manhattan = MetricQuery::<2, Manhattan>::new(&center, 0.25);
+        let mut found: Vec<_> = quadtree.query(&chebyshev).copied().collect();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![Point::new(&[0.1, 0.1]), Point::new(&[0.5, 0.25])]
+        );
+        // (0.1, 0.1) is 0.3 away under Manhattan
+        let found: Vec<_> = quadtree.query(&manhattan).copied().collect();
+        assert_eq!(found, vec![Point::new(&[0.5, 0.25])]);
+    }
+
+    #[test]
+    fn test_metric_query_try_new() {
+        let center = Point::new(&[3.0, 4.0]);
+        assert!(MetricQuery::<2, Chebyshev>::try_new(&center, -1.0).is_err());
+        assert!(MetricQuery::<2, Chebyshev>::try_new(&center, f64::NAN).is_err());
+        assert!(MetricQuery::<2, Manhattan>::try_new(&center, f64::INFINITY).is_err());
+
+        // A zero radius is an exact point query
+        let query = MetricQuery::<2, Manhattan>::try_new(&center, 0.0).unwrap();
+        assert!(query.contains(&center));
+        assert!(query.region().contains(&center));
+        assert!(!query.contains(&Point::new(&[3.0, 4.25])));
+
+        let mut quadtree = QuadTree::new(
+            &Region::new(&[
+                Interval::try_new(0.0, 10.0).unwrap(),
+                Interval::try_new(0.0, 10.0).unwrap(),
+            ]),
+            NonZero::new(1).unwrap(),
+        );
+        quadtree.insert(center).unwrap();
+        quadtree.insert(Point::new(&[3.0, 4.5])).unwrap();
+        assert_eq!(quadtree.query(&query).collect::<Vec<_>>(), vec![&center]);
+    }
+
+    #[test]
+    fn test_metric_query_euclidean_matches_distance_query() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 100.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(10).unwrap());
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
+        for _ in 0..10_000 {
+            let x = rng.random_range(0.0..100.0);
+            let y = rng.random_range(0.0..100.0);
+            quadtree.insert(TestStruct(Point::new(&[x, y]))).unwrap();
+        }
+
+        let center = Point::new(&[40.0, 60.0]);
+        let distance_query = DistanceQuery::new(&center, 25.0);
+        let metric_query = MetricQuery::<2, Euclidean>::new(&center, 25.0);
+        assert!(
+            metric_query
+                .region()
+                .contains_region(distance_query.region())
+        );
+
+        let mut expected: Vec<_> = quadtree.query(&distance_query).map(|item| item.0).collect();
+        let mut actual: Vec<_> = quadtree.query(&metric_query).map(|item| item.0).collect();
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_ellipsoid_query() {
+        let center = Point::new(&[0.0, 0.0]);
+        // Wide on x, narrow on y
+        let ellipse = EllipsoidQuery::try_new(&center, [10.0, 1.0]).unwrap();
+
+        assert_eq!(
+            ellipse.region(),
+            &Region::new(&[
+                Interval::try_new(-10.0, 10.0_f64.next_up()).unwrap(),
+                Interval::try_new(-1.0, 1.0_f64.next_up()).unwrap(),
+            ])
+        );
+
+        // 5 units away is inside on the x scale, but outside on the y scale
+        assert!(ellipse.contains(&Point::new(&[5.0, 0.0])));
+        assert!(!ellipse.contains(&Point::new(&[0.0, 5.0])));
+        assert!(ellipse.contains(&Point::new(&[10.0, 0.0])));
+
+        // Invalid radii
+        assert!(EllipsoidQuery::try_new(&center, [10.0, 0.0]).is_err());
+        assert!(EllipsoidQuery::try_new(&center, [-1.0, 1.0]).is_err());
+        assert!(EllipsoidQuery::try_new(&center, [f64::INFINITY, 1.0]).is_err());
+    }
+
+    #[test]
+    fn test_ellipsoid_query_extremes_in_tree() {
+        let mut quadtree = QuadTree::new(
+            &Region::new(&[
+                Interval::try_new(0.0, 1.0).unwrap(),
+                Interval::try_new(0.0, 1.0).unwrap(),
+            ]),
+            NonZero::new(1).unwrap(),
+        );
+        let points = [
+            Point::new(&[0.5, 0.25]),
+            Point::new(&[0.1, 0.1]),
+            Point::new(&[0.9, 0.9]),
+            Point::new(&[0.2, 0.8]),
+        ];
+        for point in points {
+            quadtree.insert(point).unwrap();
+        }
+
+        // (0.5, 0.25) is exactly on the x extreme, in a node starting at x = 0.5
+        let ellipse = EllipsoidQuery::try_new(&Point::new(&[0.25, 0.25]), [0.25, 0.25]).unwrap();
+        let mut found: Vec<_> = quadtree.query(&ellipse).copied().collect();
+        found.sort();
+        let mut expected: Vec<_> = points.into_iter().filter(|p| ellipse.contains(p)).collect();
+        expected.sort();
+        assert_eq!(found, expected);
+        assert_eq!(found.len(), 2);
+    }
+
+    #[test]
+    fn test_polygon_query() {
+        let triangle = PolygonQuery::try_new(vec![
+            Point::new(&[0.0, 0.0]),
+            Point::new(&[10.0, 0.0]),
+            Point::new(&[0.0, 10.0]),
+        ])
+        .unwrap();
+        assert!(triangle.contains(&Point::new(&[1.0, 1.0])));
+        assert!(triangle.contains(&Point::new(&[4.0, 4.0])));
+        // In the bounding box, but beyond the hypotenuse
+        assert!(!triangle.contains(&Point::new(&[6.0, 6.0])));
+        assert!(!triangle.contains(&Point::new(&[9.0, 9.0])));
+
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        for (x, y) in [(1.0, 1.0), (2.0, 5.0), (4.0, 4.0), (6.0, 6.0), (8.0, 7.0)] {
+            quadtree.insert(TestStruct(Point::new(&[x, y]))).unwrap();
+        }
+        let selected: Vec<_> = quadtree.query(&triangle).map(|item| item.0).collect();
+        assert_eq!(selected.len(), 3);
+        assert!(
+            selected
+                .iter()
+                .all(|point| point.dimension_values().iter().sum::<f64>() < 10.0)
+        );
+    }
+
+    #[test]
+    fn test_polygon_query_vertices() {
+        // Explicitly closed lists are accepted and the repeated vertex dropped
+        let closed = PolygonQuery::try_new(vec![
+            Point::new(&[0.0, 0.0]),
+            Point::new(&[1.0, 0.0]),
+            Point::new(&[1.0, 1.0]),
+            Point::new(&[0.0, 0.0]),
+        ])
+        .unwrap();
+        assert_eq!(closed.vertices().len(), 3);
+
+        assert!(
+            PolygonQuery::try_new(vec![Point::new(&[0.0, 0.0]), Point::new(&[1.0, 0.0])]).is_err()
+        );
+        assert!(
+            PolygonQuery::try_new(vec![
+                Point::new(&[0.0, 0.0]),
+                Point::new(&[1.0, 0.0]),
+                Point::new(&[0.0, 0.0]),
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_segment_query() {
+        let segment =
+            SegmentQuery::try_new(&Point::new(&[0.0, 0.0]), &Point::new(&[10.0, 0.0]), 1.0)
+                .unwrap();
+        // Near the middle
+        assert!(segment.contains(&Point::new(&[5.0, 0.9])));
+        assert!(segment.contains(&Point::new(&[5.0, -1.0])));
+        assert!(!segment.contains(&Point::new(&[5.0, 1.1])));
+        // Near an endpoint, within the rounded cap
+        assert!(segment.contains(&Point::new(&[10.5, 0.5])));
+        assert!(segment.contains(&Point::new(&[-0.6, 0.6])));
+        // Beyond an endpoint, though still inside the bounding region
+        assert!(!segment.contains(&Point::new(&[10.8, 0.8])));
+        assert!(segment.region().contains(&Point::new(&[10.8, 0.8])));
+
+        assert!(
+            SegmentQuery::try_new(&Point::new(&[0.0, 0.0]), &Point::new(&[1.0, 1.0]), 0.0).is_err()
+        );
+    }
+
+    #[test]
+    fn test_segment_query_degenerate() {
+        let point = Point::new(&[3.0, 3.0]);
+        let segment = SegmentQuery::try_new(&point, &point, 2.0).unwrap();
+        assert!(segment.contains(&Point::new(&[4.0, 4.0])));
+        assert!(!segment.contains(&Point::new(&[5.0, 5.0])));
+    }
+
+    fn grid_quadtree() -> QuadTree<2, TestStruct> {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        for (x, y) in (0..10).cartesian_product(0..10) {
+            quadtree.insert(TestStruct(Point::new(&[x, y]))).unwrap();
+        }
+        quadtree
+    }
+
+    #[test]
+    fn test_and_query() {
+        let quadtree = grid_quadtree();
+        let square = Region::new(&[
+            Interval::try_new(0.0, 5.0).unwrap(),
+            Interval::try_new(0.0, 5.0).unwrap(),
+        ]);
+        let circle = DistanceQuery::new(&Point::new(&[5.0, 5.0]), 2.5);
+
+        // Both orders select the same points: those in the square and in the circle
+        let and_query = AndQuery::new(square.clone(), circle.clone());
+        let results: Vec<_> = quadtree
+            .query(&and_query)
+            .map(|item| item.0)
+            .sorted()
+            .collect();
+        let expected: Vec<_> = [[3.0, 4.0], [4.0, 3.0], [4.0, 4.0]]
+            .iter()
+            .map(Point::new)
+            .collect();
+        assert_eq!(results, expected);
+        let reversed = AndQuery::new(circle, square);
+        assert_eq!(quadtree.query(&reversed).count(), results.len());
+
+        // Disjoint queries select nothing
+        let far_circle = DistanceQuery::new(&Point::new(&[9.0, 9.0]), 1.0);
+        let disjoint = AndQuery::new(
+            Region::new(&[
+                Interval::try_new(0.0, 2.0).unwrap(),
+                Interval::try_new(0.0, 2.0).unwrap(),
+            ]),
+            far_circle,
+        );
+        assert_eq!(quadtree.query(&disjoint).count(), 0);
+    }
+
+    #[test]
+    fn test_or_query() {
+        let quadtree = grid_quadtree();
+        let square = Region::new(&[
+            Interval::try_new(0.0, 2.0).unwrap(),
+            Interval::try_new(0.0, 2.0).unwrap(),
+        ]);
+        let circle = DistanceQuery::new(&Point::new(&[7.0, 7.0]), 1.0);
+
+        let or_query = OrQuery::new(square.clone(), circle.clone());
+        assert_eq!(
+            or_query.region().intervals()[0],
+            Interval::try_new(0.0, 8.0).unwrap()
+        );
+        // 4 in the square plus 5 in the circle
+        assert_eq!(quadtree.query(&or_query).count(), 9);
+        assert_eq!(quadtree.query(&OrQuery::new(circle, square)).count(), 9);
+    }
+
+    #[test]
+    fn test_query_edge_on_subdivision_boundary() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(1).unwrap());
+        for point in [[1.0, 1.0], [5.0, 2.0], [8.0, 8.0], [10.0, 5.0]] {
+            quadtree.insert(TestStruct(Point::new(&point))).unwrap();
+        }
+        let found = |query: &Region<2>| {
+            quadtree
+                .query(query)
+                .map(|item| item.0)
+                .sorted()
+                .collect_vec()
+        };
+
+        // (5, 2) is on the internal x = 5 boundary, so belongs to the right-hand subtrees
+        let right = Region::new(&[
+            Interval::try_new(5.0, 7.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        assert_eq!(found(&right), vec![Point::new(&[5.0, 2.0])]);
+        let left = Region::new(&[
+            Interval::try_new(3.0, 5.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        assert!(found(&left).is_empty());
+
+        // (10, 5) is on the tree's upper edge, held by a node whose half-open region ends at 10
+        let beyond = Region::new(&[
+            Interval::try_new(10.0, 12.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        assert_eq!(found(&beyond), vec![Point::new(&[10.0, 5.0])]);
+    }
+
+    #[test]
+    fn test_region_implies_contains_short_circuit() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(11);
+        let region = Region::new(&[
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 100.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(4).unwrap());
+        for _ in 0..2000 {
+            quadtree
+                .insert(TestStruct(region.sample_point(&mut rng)))
+                .unwrap();
+        }
+
+        let selection = Region::new(&[
+            Interval::try_new(10.0, 80.0).unwrap(),
+            Interval::try_new(0.0, 60.0).unwrap(),
+        ]);
+        // The same selection, but without declaring that its region implies containment
+        let unoptimised = FnQuery::new(selection.clone(), |point| selection.contains(point));
+        assert!(selection.region_implies_contains());
+        assert!(!unoptimised.region_implies_contains());
+
+        let inner = Region::new(&[
+            Interval::try_new(25.0, 50.0).unwrap(),
+            Interval::try_new(25.0, 50.0).unwrap(),
+        ]);
+        assert_eq!(selection.region_relationship(&inner), Containment::Inside);
+        assert_eq!(
+            unoptimised.region_relationship(&inner),
+            Containment::Partial
+        );
+
+        let optimised: Vec<_> = quadtree
+            .query(&selection)
+            .map(|item| item.0)
+            .sorted()
+            .collect();
+        let expected: Vec<_> = quadtree
+            .query(&unoptimised)
+            .map(|item| item.0)
+            .sorted()
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(optimised, expected);
+    }
+
+    #[test]
+    fn test_not_query() {
+        let quadtree = grid_quadtree();
+        let square = Region::new(&[
+            Interval::try_new(2.0, 7.0).unwrap(),
+            Interval::try_new(2.0, 7.0).unwrap(),
+        ]);
+        let circle = DistanceQuery::new(&Point::new(&[4.0, 4.0]), 1.0);
+        let not_query = NotQuery::new(circle.clone(), square.clone());
+
+        let results: Vec<_> = quadtree.query(&not_query).map(|item| item.0).collect();
+        // 25 points in the square, minus the centre and its 4 neighbours
+        assert_eq!(results.len(), 20);
+        assert!(
+            results
+                .iter()
+                .all(|point| square.contains(point) && !circle.contains(point))
+        );
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn test_geo_polygon_query() {
+        let quadtree = grid_quadtree();
+        let triangle = geo::Polygon::new(
+            geo::LineString::from(vec![(0.5, 0.5), (8.5, 0.5), (0.5, 8.5)]),
+            vec![],
+        );
+        let query = GeoPolygonQuery::try_new(triangle).unwrap();
+        let results: Vec<_> = quadtree.query(&query).map(|item| item.0).collect();
+        // Grid points with x, y >= 1 and x + y <= 8
+        assert_eq!(results.len(), 28);
+        assert!(results.iter().all(|point| {
+            let [x, y] = point.to_array();
+            x >= 1.0 && y >= 1.0 && x + y <= 8.0
+        }));
+
+        let empty = geo::Polygon::new(geo::LineString::new(vec![]), vec![]);
+        assert!(GeoPolygonQuery::try_new(empty).is_err());
+    }
+
+    #[test]
+    fn test_distance_query_getters() {
+        let center = Point::new(&[1.0, -2.0]);
+        let query = DistanceQuery::new(&center, 3.5);
+        assert_eq!(query.center(), &center);
+        assert_eq!(query.radius(), 3.5);
+
+        // e.g. growing a query handed to us
+        let grown = DistanceQuery::new(query.center(), query.radius() * 2.0);
+        assert_eq!(grown.radius(), 7.0);
+    }
+
+    #[test]
+    fn test_distance_query_try_new() {
+        let center = Point::new(&[3.0, 4.0]);
+        assert!(DistanceQuery::try_new(&center, -1.0).is_err());
+        assert!(DistanceQuery::try_new(&center, f64::NAN).is_err());
+        assert!(DistanceQuery::try_new(&center, f64::INFINITY).is_err());
+
+        // A zero radius is an exact point query
+        let query = DistanceQuery::try_new(&center, 0.0).unwrap();
+        assert!(query.contains(&center));
+        assert!(query.region().contains(&center));
+        assert!(!query.contains(&Point::new(&[3.0, 4.25])));
+
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(1).unwrap());
+        quadtree.insert(center).unwrap();
+        quadtree.insert(Point::new(&[3.0, 4.5])).unwrap();
+        assert_eq!(quadtree.query(&query).collect::<Vec<_>>(), vec![&center]);
+    }
+
+    #[test]
+    fn test_surface_query() {
+        let center = Point::new(&[50.0, 50.0]);
+        let surface = SurfaceQuery::new(&center, 10.0, 0.5);
+        let solid = DistanceQuery::new(&center, 10.5);
+        assert_eq!(surface.region(), solid.region());
+
+        let region = Region::new(&[
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 100.0).unwrap(),
+        ]);
+        let mut quadtree = QuadTree::new(&region, NonZero::new(2).unwrap());
+        let on_surface = [
+            Point::new(&[60.0, 50.0]),
+            Point::new(&[50.0, 39.6]),
+            Point::new(&[40.0, 50.0]),
+        ];
+        let inside = [
+            center,
+            Point::new(&[52.0, 53.0]),
+            Point::new(&[45.0, 50.0]),
+            Point::new(&[50.0, 59.4]),
+        ];
+        let outside = [Point::new(&[61.0, 50.0]), Point::new(&[90.0, 90.0])];
+        for point in on_surface.iter().chain(&inside).chain(&outside) {
+            quadtree.insert(*point).unwrap();
+        }
+
+        let mut found = quadtree.query(&surface).copied().collect::<Vec<_>>();
+        found.sort();
+        let mut expected = on_surface.to_vec();
+        expected.sort();
+        assert_eq!(found, expected);
+
+        // The solid sphere also takes the points inside the shell
+        assert_eq!(
+            quadtree.query(&solid).count(),
+            on_surface.len() + inside.len()
+        );
+
+        assert!(SurfaceQuery::try_new(&center, 10.0, -0.5).is_err());
+        assert!(SurfaceQuery::try_new(&center, f64::NAN, 0.5).is_err());
+        assert!(SurfaceQuery::try_new(&center, 0.0, 0.0).is_ok());
+    }
+
+    #[test]
+    fn test_circle_boundary_inclusive() {
+        // A 3-4-5 triangle puts the point exactly on the circle
+        let circle_query = DistanceQuery::new(&Point::new(&[0.0, 0.0]), 5.0);
+        assert!(circle_query.contains(&Point::new(&[3.0, 4.0])));
+        assert!(circle_query.contains(&Point::new(&[-5.0, 0.0])));
+        assert!(!circle_query.contains(&Point::new(&[3.0, 4.0_f64.next_up()])));
+        assert!(!circle_query.contains(&Point::new(&[5.0_f64.next_up(), 0.0])));
+    }
+
+    #[test]
+    fn test_circle_region_relationship() {
+        let circle_query = DistanceQuery::new(&Point::new(&[0.0, 0.0]), 10.0);
+
+        let inside = Region::new(&[
+            Interval::try_new(-1.0, 1.0).unwrap(),
+            Interval::try_new(-1.0, 1.0).unwrap(),
+        ]);
+        assert_eq!(
+            circle_query.region_relationship(&inside),
+            Containment::Inside
+        );
+
+        // Inside the bounding box, but beyond the circle in the corner
+        let outside = Region::new(&[
+            Interval::try_new(8.0, 10.0).unwrap(),
+            Interval::try_new(8.0, 10.0).unwrap(),
+        ]);
+        assert_eq!(
+            circle_query.region_relationship(&outside),
+            Containment::Outside
+        );
+
+        let partial = Region::new(&[
+            Interval::try_new(5.0, 15.0).unwrap(),
+            Interval::try_new(-1.0, 1.0).unwrap(),
+        ]);
+        assert_eq!(
+            circle_query.region_relationship(&partial),
+            Containment::Partial
+        );
+    }
+
     #[test]
     fn test_circle_query() {
         let center = Point::new(&[5.0, 5.0]);
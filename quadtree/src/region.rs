@@ -1,18 +1,33 @@
-use crate::{interval::Interval, point::Point, query::Query};
+use crate::{interval::Interval, point::Point, query::Query, scalar::Scalar};
+use approx::abs_diff_eq;
 use eyre::{Result, ensure};
 use itertools::Itertools;
 use rand::{Rng, distr::uniform::SampleRange};
+use std::fmt::Display;
 
 /// A region in n-dimensional space defined by a Vec of intervals.
 #[derive(Debug, Clone, PartialEq)]
-pub struct Region<const N: usize>([Interval; N]);
+pub struct Region<const N: usize, F = f64>([Interval<F>; N]);
 
-impl<const N: usize> Region<N> {
-    pub fn new(intervals: &[Interval; N]) -> Self {
+impl<const N: usize, F: Scalar> Display for Region<N, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Region({})",
+            self.0
+                .iter()
+                .map(|interval| interval.to_string())
+                .join(" x ")
+        )
+    }
+}
+
+impl<const N: usize, F: Scalar> Region<N, F> {
+    pub fn new(intervals: &[Interval<F>; N]) -> Self {
         Region(*intervals)
     }
 
-    pub fn try_new(intervals: &[Interval]) -> Result<Self> {
+    pub fn try_new(intervals: &[Interval<F>]) -> Result<Self> {
         ensure!(
             intervals.len() == N,
             "cannot create region of size {} from Vec of size {}",
@@ -28,64 +43,265 @@ impl<const N: usize> Region<N> {
         ))
     }
 
-    pub fn intervals(&self) -> &[Interval; N] {
+    /// Create a [Region] from two opposite corners, given in any order.
+    /// Errors if the corners share a coordinate on any axis.
+    pub fn from_corners(min: &Point<N, F>, max: &Point<N, F>) -> Result<Self> {
+        let intervals = min
+            .dimension_values()
+            .iter()
+            .zip(max.dimension_values())
+            .map(|(&a, &b)| Interval::try_new(a.min(b), a.max(b)))
+            .collect::<Result<Vec<_>>>()?;
+        Self::try_new(&intervals)
+    }
+
+    /// The tightest [Region] enclosing all the points.
+    /// Each axis end is nudged up to the next representable value so that the half-open
+    /// intervals still contain the extreme points. Errors on an empty slice.
+    pub fn bounding(points: &[Point<N, F>]) -> Result<Self> {
+        ensure!(
+            !points.is_empty(),
+            "cannot create a bounding region from no points"
+        );
+        let intervals = (0..N)
+            .map(|dimension| {
+                let (min, max) = points
+                    .iter()
+                    .map(|point| point.dimension_values()[dimension])
+                    .fold((F::infinity(), F::neg_infinity()), |(min, max), value| {
+                        (min.min(value), max.max(value))
+                    });
+                Interval::try_new(min, max.next_up())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::try_new(&intervals)
+    }
+
+    pub fn intervals(&self) -> &[Interval<F>; N] {
         &self.0
     }
 
-    pub fn contains(&self, point: &Point<N>) -> bool {
+    /// Half-open containment on every axis, see [Interval::contains]
+    pub fn contains(&self, point: &Point<N, F>) -> bool {
         self.intervals()
             .iter()
             .zip(point.dimension_values())
             .all(|(interval, value)| interval.contains(value))
     }
 
-    pub fn subdivide(&self) -> Vec<[Interval; N]> {
-        let iterators = self
-            .intervals()
+    /// Closed containment on every axis, see [Interval::contains_inclusive]
+    pub fn contains_inclusive(&self, point: &Point<N, F>) -> bool {
+        self.intervals()
             .iter()
-            .map(|interval| interval.subdivide())
-            .collect::<Vec<_>>();
+            .zip(point.dimension_values())
+            .all(|(interval, value)| interval.contains_inclusive(value))
+    }
 
-        iterators
+    pub fn subdivide(&self) -> Vec<[Interval<F>; N]> {
+        self.subdivide_iter().map(|region| region.0).collect()
+    }
+
+    /// Lazily yields the (up to 2^N) child regions from splitting every axis at its mid-point
+    pub fn subdivide_iter(&self) -> impl Iterator<Item = Region<N, F>> {
+        self.intervals()
             .iter()
-            .map(|v| v.iter().cloned())
+            .map(|interval| interval.subdivide().into_iter())
             .multi_cartesian_product()
             .map(|product| {
-                product
-                    .into_iter()
-                    .collect_array()
-                    .expect("same sized array")
+                Region(
+                    product
+                        .into_iter()
+                        .collect_array()
+                        .expect("same sized array"),
+                )
+            })
+    }
+
+    /// Returns the 2N boundary faces of the [Region] as `(dimension, is_upper, face)`.
+    /// Each face pins its dimension to the start (or end) value with
+    /// [Interval::containing_only], so it contains exactly that value.
+    /// Errors if a bound is the largest finite value, as no interval holds only that value.
+    pub fn faces(&self) -> Result<Vec<(usize, bool, Region<N, F>)>> {
+        (0..N)
+            .cartesian_product([false, true])
+            .map(|(dimension, is_upper)| {
+                let mut intervals = self.0;
+                let interval = &self.0[dimension];
+                let value = if is_upper {
+                    *interval.end()
+                } else {
+                    *interval.start()
+                };
+                intervals[dimension] = Interval::containing_only(value)?;
+                Ok((dimension, is_upper, Region(intervals)))
+            })
+            .collect()
+    }
+
+    /// The point halfway along every interval
+    pub fn center(&self) -> Point<N, F> {
+        let values: [F; N] = std::array::from_fn(|i| self.0[i].start().midpoint(*self.0[i].end()));
+        Point::from(values)
+    }
+
+    /// Shift every interval by the offset for its dimension
+    pub fn translate(&self, offset: &[F; N]) -> Result<Region<N, F>> {
+        let intervals = self
+            .intervals()
+            .iter()
+            .zip(offset)
+            .map(|(interval, offset)| {
+                Interval::try_new(*interval.start() + *offset, *interval.end() + *offset)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::try_new(&intervals)
+    }
+
+    /// Grow (factor > 1.0) or shrink (factor < 1.0) every interval about the region's center.
+    /// Errors if the factor is not positive and finite, as the region would collapse or invert.
+    pub fn scale_about_center(&self, factor: F) -> Result<Region<N, F>> {
+        ensure!(
+            factor.is_finite() && factor > F::zero(),
+            "Scale factor must be positive and finite, got {}",
+            factor
+        );
+        let intervals = self
+            .intervals()
+            .iter()
+            .map(|interval| {
+                let center = interval.start().midpoint(*interval.end());
+                let half = interval.length() * factor / F::from_f64(2.0);
+                Interval::try_new(center - half, center + half)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::try_new(&intervals)
+    }
+
+    /// The length of each interval, see [Interval::length]
+    pub fn extents(&self) -> [F; N] {
+        std::array::from_fn(|i| self.0[i].length())
+    }
+
+    /// Ratio of the longest extent to the shortest, 1.0 for a hypercube.
+    /// Large values mean subdivision will produce long, thin cells.
+    pub fn max_aspect_ratio(&self) -> F {
+        let extents = self.extents();
+        let longest = extents.iter().copied().fold(F::min_value(), F::max);
+        let shortest = extents.iter().copied().fold(F::max_value(), F::min);
+        longest / shortest
+    }
+
+    /// Grow every interval by `margin` on both sides.
+    /// A negative margin shrinks the region, and errors if it would invert an interval.
+    pub fn expand(&self, margin: F) -> Result<Region<N, F>> {
+        let intervals = self
+            .intervals()
+            .iter()
+            .map(|interval| Interval::try_new(*interval.start() - margin, *interval.end() + margin))
+            .collect::<Result<Vec<_>>>()?;
+        Self::try_new(&intervals)
+    }
+
+    /// Euclidean distance from the point to the nearest face or corner of the Region, 0.0 if inside.
+    /// Nothing stored in the Region can be closer to the point than this.
+    pub fn min_distance(&self, point: &Point<N, F>) -> F {
+        self.intervals()
+            .iter()
+            .zip(point.dimension_values())
+            .map(|(interval, value)| {
+                let gap = (*interval.start() - *value)
+                    .max(*value - *interval.end())
+                    .max(F::zero());
+                gap * gap
+            })
+            .sum::<F>()
+            .sqrt()
+    }
+
+    /// Whether `other` lies entirely within this Region on every axis, see [Interval::contains_interval]
+    pub fn contains_region(&self, other: &Region<N, F>) -> bool {
+        self.intervals()
+            .iter()
+            .zip(other.intervals().iter())
+            .all(|(a, b)| a.contains_interval(b))
+    }
+
+    /// Whether every interval bound is within `epsilon` of the other's, tolerating the
+    /// floating point noise that exact `==` doesn't
+    pub fn approx_eq(&self, other: &Region<N, F>, epsilon: F) -> bool {
+        self.intervals()
+            .iter()
+            .zip(other.intervals().iter())
+            .all(|(a, b)| {
+                abs_diff_eq!(a.start(), b.start(), epsilon = epsilon)
+                    && abs_diff_eq!(a.end(), b.end(), epsilon = epsilon)
             })
-            .collect::<Vec<_>>()
     }
 
-    pub fn intersects(&self, other: &Region<N>) -> bool {
+    pub fn intersects(&self, other: &Region<N, F>) -> bool {
         self.intervals()
             .iter()
             .zip(other.intervals().iter())
             .all(|(a, b)| a.intersects(b))
     }
 
-    pub fn sample_point(&self, rng: &mut impl Rng) -> Point<N> {
-        let values: Vec<f64> = self
+    /// Whether `other` overlaps this Region taken as closed on every axis,
+    /// see [Interval::intersects_inclusive]
+    pub fn intersects_inclusive(&self, other: &Region<N, F>) -> bool {
+        self.intervals()
+            .iter()
+            .zip(other.intervals().iter())
+            .all(|(a, b)| a.intersects_inclusive(b))
+    }
+
+    /// Returns the smallest Region covering both, see [Interval::merge]
+    pub fn merge(&self, other: &Region<N, F>) -> Region<N, F> {
+        Region(std::array::from_fn(|i| self.0[i].merge(&other.0[i])))
+    }
+
+    /// Returns the overlapping Region, or None if the regions do not overlap on every axis
+    pub fn intersection(&self, other: &Region<N, F>) -> Option<Region<N, F>> {
+        let intervals = self
             .intervals()
             .iter()
-            .map(|interval| interval.sample_single(rng).unwrap())
-            .collect();
-        Point::try_new(&values).expect("should be same size as N")
+            .zip(other.intervals().iter())
+            .map(|(a, b)| a.intersection(b))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Region(intervals.into_iter().collect_array()?))
+    }
+
+    /// The volume (area in 2D) of the overlap with `other`, zero if they don't intersect.
+    /// Useful for overlap metrics such as intersection over union.
+    pub fn intersection_volume(&self, other: &Region<N, F>) -> F {
+        self.intersection(other).map_or(F::zero(), |overlap| {
+            overlap
+                .extents()
+                .into_iter()
+                .fold(F::one(), |volume, extent| volume * extent)
+        })
+    }
+
+    pub fn sample_point(&self, rng: &mut impl Rng) -> Point<N, F> {
+        let values: [F; N] = std::array::from_fn(|i| self.0[i].sample_single(rng).unwrap());
+        Point::from(values)
     }
 }
 
 /// We can trivially implement [Query] for [Region]
-/// This allows us to use Region in a QuadTree query
-impl<const N: usize> Query<N> for Region<N> {
-    fn region(&self) -> &Region<N> {
+/// This allows us to use Region in a QuadTree query.
+/// Containment is half-open, use [crate::query::InclusiveRegion] to include the upper bounds.
+impl<const N: usize, F: Scalar> Query<N, F> for Region<N, F> {
+    fn region(&self) -> &Region<N, F> {
         self
     }
 
-    fn contains(&self, point: &Point<N>) -> bool {
+    fn contains(&self, point: &Point<N, F>) -> bool {
         self.contains(point)
     }
+
+    fn region_implies_contains(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(feature = "nannou")]
@@ -117,6 +333,30 @@ impl From<nannou::geom::Rect> for Region<2> {
     }
 }
 
+#[cfg(feature = "geo")]
+impl From<&Region<2>> for geo::Rect<f64> {
+    fn from(region: &Region<2>) -> Self {
+        let [x, y] = region.intervals();
+        geo::Rect::new(
+            geo::coord! { x: *x.start(), y: *y.start() },
+            geo::coord! { x: *x.end(), y: *y.end() },
+        )
+    }
+}
+
+/// Fallible unlike the nannou conversion, as a geo::Rect may be degenerate (zero width or height)
+#[cfg(feature = "geo")]
+impl TryFrom<geo::Rect<f64>> for Region<2> {
+    type Error = eyre::Report;
+
+    fn try_from(rect: geo::Rect<f64>) -> Result<Self> {
+        Ok(Region::new(&[
+            Interval::try_new(rect.min().x, rect.max().x)?,
+            Interval::try_new(rect.min().y, rect.max().y)?,
+        ]))
+    }
+}
+
 /// Demonstrates region containment with correct and incorrect point dimensions.
 ///
 /// This compiles:
@@ -159,6 +399,46 @@ mod tests {
         assert!(!region.contains(&point_outside));
     }
 
+    #[test]
+    fn test_region_contains_inclusive() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let corner = Point::new(&[10.0, 10.0]);
+        assert!(!region.contains(&corner));
+        assert!(region.contains_inclusive(&corner));
+    }
+
+    #[test]
+    fn test_bounding() {
+        let points = vec![
+            Point::new(&[1.0, 20.0]),
+            Point::new(&[5.0, 35.0]),
+            Point::new(&[3.0, 60.0]),
+        ];
+        let region = Region::bounding(&points).unwrap();
+        assert_eq!(*region.intervals()[0].start(), 1.0);
+        assert_eq!(*region.intervals()[1].start(), 20.0);
+        // Every point is contained, including those on the upper extremes
+        assert!(points.iter().all(|point| region.contains(point)));
+
+        // A single point still produces a valid region
+        let single = Region::bounding(&points[..1]).unwrap();
+        assert!(single.contains(&points[0]));
+
+        assert!(Region::<2>::bounding(&[]).is_err());
+    }
+
+    #[test]
+    fn test_region_display() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.5, 20.0).unwrap(),
+        ]);
+        assert_eq!(region.to_string(), "Region([0, 10) x [0.5, 20))");
+    }
+
     #[test]
     fn test_region_2d_subdivide() {
         let x_axis = Interval::try_new(1.0, 5.0).unwrap();
@@ -213,6 +493,204 @@ mod tests {
         assert_eq!(unique_intervals.len(), 8);
     }
 
+    #[test]
+    fn test_region_3d_subdivide_iter() {
+        let x_axis = Interval::try_new(1.0, 5.0).unwrap();
+        let y_axis = Interval::try_new(20.0, 60.0).unwrap();
+        let z_axis = Interval::try_new(100.0, 200.0).unwrap();
+        let region = Region::new(&[x_axis, y_axis, z_axis]);
+
+        let bounds = |intervals: &[Interval; 3]| {
+            intervals
+                .iter()
+                .map(|i| format!("{} to {}", i.start(), i.end()))
+                .collect::<Vec<String>>()
+        };
+        let from_iter: Vec<_> = region
+            .subdivide_iter()
+            .map(|child| bounds(child.intervals()))
+            .sorted()
+            .collect();
+        let from_vec: Vec<_> = region.subdivide().iter().map(bounds).sorted().collect();
+        assert_eq!(from_iter.len(), 8);
+        assert_eq!(from_iter, from_vec);
+    }
+
+    #[test]
+    fn test_faces() {
+        let x_axis = Interval::try_new(1.0, 5.0).unwrap();
+        let y_axis = Interval::try_new(20.0, 60.0).unwrap();
+        let z_axis = Interval::try_new(100.0, 200.0).unwrap();
+
+        let faces_2d = Region::new(&[x_axis, y_axis]).faces().unwrap();
+        assert_eq!(faces_2d.len(), 4);
+        let faces_3d = Region::new(&[x_axis, y_axis, z_axis]).faces().unwrap();
+        assert_eq!(faces_3d.len(), 6);
+
+        // The upper x face contains points on the x = 5 boundary only
+        let (dimension, is_upper, face) = &faces_2d[1];
+        assert_eq!((*dimension, *is_upper), (0, true));
+        assert!(face.contains(&Point::new(&[5.0, 30.0])));
+        assert!(!face.contains(&Point::new(&[4.0, 30.0])));
+    }
+
+    #[test]
+    fn test_faces_at_max() {
+        // A valid region, but its upper x face can't be pinned to a single value
+        let region = Region::new(&[
+            Interval::try_new(1.0, f64::MAX).unwrap(),
+            Interval::try_new(20.0, 60.0).unwrap(),
+        ]);
+        assert!(region.faces().is_err());
+
+        // The lower bound can be the most negative value, as it has a value above it
+        let region = Region::new(&[
+            Interval::try_new(f64::MIN, 5.0).unwrap(),
+            Interval::try_new(20.0, 60.0).unwrap(),
+        ]);
+        assert_eq!(region.faces().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_expand() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let just_outside = Point::new(&[10.5, -0.5]);
+        assert!(!region.contains(&just_outside));
+
+        let expanded = region.expand(1.0).unwrap();
+        assert!(expanded.contains(&just_outside));
+        assert_eq!(*expanded.intervals()[0].start(), -1.0);
+        assert_eq!(*expanded.intervals()[0].end(), 11.0);
+
+        // Shrinking is allowed until an interval would invert
+        assert!(region.expand(-4.0).is_ok());
+        assert!(region.expand(-5.0).is_err());
+    }
+
+    #[test]
+    fn test_extents_and_aspect_ratio() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 1.0).unwrap(),
+        ]);
+        assert_eq!(region.extents(), [100.0, 1.0]);
+        assert_eq!(region.max_aspect_ratio(), 100.0);
+
+        let square = Region::new(&[
+            Interval::try_new(-5.0, 5.0).unwrap(),
+            Interval::try_new(10.0, 20.0).unwrap(),
+        ]);
+        assert_eq!(square.max_aspect_ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_translate() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 4.0).unwrap(),
+        ]);
+        assert_eq!(region.center(), Point::new(&[5.0, 2.0]));
+
+        let translated = region.translate(&[3.0, -1.0]).unwrap();
+        assert_eq!(translated.center(), Point::new(&[8.0, 1.0]));
+        assert_eq!(translated.extents(), region.extents());
+
+        assert!(region.translate(&[f64::INFINITY, 0.0]).is_err());
+    }
+
+    #[test]
+    fn test_scale_about_center() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 4.0).unwrap(),
+        ]);
+        let doubled = region.scale_about_center(2.0).unwrap();
+        assert_eq!(doubled.extents(), [20.0, 8.0]);
+        assert_eq!(doubled.center(), region.center());
+
+        let halved = region.scale_about_center(0.5).unwrap();
+        assert_eq!(halved.extents(), [5.0, 2.0]);
+
+        assert!(region.scale_about_center(0.0).is_err());
+        assert!(region.scale_about_center(-1.0).is_err());
+        assert!(region.scale_about_center(f64::NAN).is_err());
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn test_geo_rect_round_trip() {
+        let region = Region::new(&[
+            Interval::try_new(-1.0, 4.0).unwrap(),
+            Interval::try_new(2.0, 3.5).unwrap(),
+        ]);
+        let rect: geo::Rect<f64> = (&region).into();
+        assert_eq!(rect.min(), geo::coord! { x: -1.0, y: 2.0 });
+        assert_eq!(rect.max(), geo::coord! { x: 4.0, y: 3.5 });
+        assert_eq!(Region::try_from(rect).unwrap(), region);
+
+        let degenerate = geo::Rect::new(
+            geo::coord! { x: 1.0, y: 0.0 },
+            geo::coord! { x: 1.0, y: 2.0 },
+        );
+        assert!(Region::try_from(degenerate).is_err());
+    }
+
+    #[test]
+    fn test_min_distance() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        // Inside
+        assert_eq!(region.min_distance(&Point::new(&[5.0, 5.0])), 0.0);
+        // Beside a face
+        assert_eq!(region.min_distance(&Point::new(&[13.0, 5.0])), 3.0);
+        assert_eq!(region.min_distance(&Point::new(&[5.0, -2.0])), 2.0);
+        // Diagonally off a corner
+        assert_eq!(region.min_distance(&Point::new(&[-3.0, 14.0])), 5.0);
+    }
+
+    #[test]
+    fn test_contains_region() {
+        let outer = Region::new(&[
+            Interval::try_new(0.0, 10.0).unwrap(),
+            Interval::try_new(0.0, 10.0).unwrap(),
+        ]);
+        let inner = Region::new(&[
+            Interval::try_new(2.0, 10.0).unwrap(),
+            Interval::try_new(3.0, 4.0).unwrap(),
+        ]);
+        let overhanging = Region::new(&[
+            Interval::try_new(2.0, 5.0).unwrap(),
+            Interval::try_new(3.0, 11.0).unwrap(),
+        ]);
+        assert!(outer.contains_region(&outer));
+        assert!(outer.contains_region(&inner));
+        assert!(!inner.contains_region(&outer));
+        assert!(!outer.contains_region(&overhanging));
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let region = Region::new(&[
+            Interval::try_new(1.0, 2.0).unwrap(),
+            Interval::try_new(0.0, 4.0).unwrap(),
+        ]);
+        let noisy = Region::new(&[
+            Interval::try_new(1.0 + f64::EPSILON, 2.0).unwrap(),
+            Interval::try_new(0.0, 4.0).unwrap(),
+        ]);
+        assert_ne!(region, noisy);
+        assert!(region.approx_eq(&noisy, 1e-12));
+        assert!(!region.approx_eq(&noisy, 0.0));
+
+        let shifted = region.translate(&[0.1, 0.0]).unwrap();
+        assert!(!region.approx_eq(&shifted, 1e-12));
+    }
+
     #[test]
     fn test_intersects() {
         let x_axis = Interval::try_new(1.0, 5.0).unwrap();
@@ -226,6 +704,102 @@ mod tests {
         assert!(!region_a.intersects(&region_c));
     }
 
+    #[test]
+    fn test_from_corners() {
+        let expected = Region::new(&[
+            Interval::try_new(1.0, 5.0).unwrap(),
+            Interval::try_new(20.0, 60.0).unwrap(),
+        ]);
+
+        let region_a =
+            Region::from_corners(&Point::new(&[1.0, 20.0]), &Point::new(&[5.0, 60.0])).unwrap();
+        let region_b =
+            Region::from_corners(&Point::new(&[5.0, 20.0]), &Point::new(&[1.0, 60.0])).unwrap();
+        assert_eq!(region_a, expected);
+        assert_eq!(region_b, expected);
+
+        // Corners sharing a coordinate cannot form a region
+        let flat = Region::from_corners(&Point::new(&[1.0, 20.0]), &Point::new(&[5.0, 20.0]));
+        assert!(flat.is_err());
+    }
+
+    #[test]
+    fn test_intersection() {
+        let x_axis = Interval::try_new(1.0, 5.0).unwrap();
+        let y_axis = Interval::try_new(20.0, 60.0).unwrap();
+        let region_a = Region::new(&[x_axis, y_axis]);
+
+        // Partial overlap
+        let region_b = Region::new(&[
+            Interval::try_new(4.0, 6.0).unwrap(),
+            Interval::try_new(10.0, 30.0).unwrap(),
+        ]);
+        assert_eq!(
+            region_a.intersection(&region_b),
+            Some(Region::new(&[
+                Interval::try_new(4.0, 5.0).unwrap(),
+                Interval::try_new(20.0, 30.0).unwrap(),
+            ]))
+        );
+
+        // Containment
+        let region_c = Region::new(&[
+            Interval::try_new(2.0, 3.0).unwrap(),
+            Interval::try_new(30.0, 40.0).unwrap(),
+        ]);
+        assert_eq!(region_a.intersection(&region_c), Some(region_c.clone()));
+
+        // Disjoint on a single axis is enough to have no intersection
+        let region_d = Region::new(&[Interval::try_new(6.0, 8.0).unwrap(), y_axis]);
+        assert_eq!(region_a.intersection(&region_d), None);
+    }
+
+    #[test]
+    fn test_intersection_volume() {
+        let region_a = Region::new(&[
+            Interval::try_new(1.0, 5.0).unwrap(),
+            Interval::try_new(20.0, 60.0).unwrap(),
+        ]);
+        let region_b = Region::new(&[
+            Interval::try_new(4.0, 6.0).unwrap(),
+            Interval::try_new(10.0, 30.0).unwrap(),
+        ]);
+        assert_eq!(region_a.intersection_volume(&region_b), 10.0);
+        assert_eq!(region_b.intersection_volume(&region_a), 10.0);
+        assert_eq!(region_a.intersection_volume(&region_a), 160.0);
+
+        // Disjoint, and merely touching, regions don't overlap
+        let disjoint = Region::new(&[
+            Interval::try_new(6.0, 8.0).unwrap(),
+            Interval::try_new(20.0, 60.0).unwrap(),
+        ]);
+        assert_eq!(region_a.intersection_volume(&disjoint), 0.0);
+        let touching = Region::new(&[
+            Interval::try_new(5.0, 8.0).unwrap(),
+            Interval::try_new(20.0, 60.0).unwrap(),
+        ]);
+        assert_eq!(region_a.intersection_volume(&touching), 0.0);
+    }
+
+    #[test]
+    fn test_merge() {
+        let region_a = Region::new(&[
+            Interval::try_new(1.0, 5.0).unwrap(),
+            Interval::try_new(20.0, 60.0).unwrap(),
+        ]);
+        let region_b = Region::new(&[
+            Interval::try_new(8.0, 9.0).unwrap(),
+            Interval::try_new(30.0, 40.0).unwrap(),
+        ]);
+        assert_eq!(
+            region_a.merge(&region_b),
+            Region::new(&[
+                Interval::try_new(1.0, 9.0).unwrap(),
+                Interval::try_new(20.0, 60.0).unwrap(),
+            ])
+        );
+    }
+
     #[test]
     fn test_sample_point() {
         let x_axis = Interval::try_new(1.0, 5.0).unwrap();
@@ -244,4 +818,19 @@ mod tests {
         // Ensure we sampled multiple unique points
         assert_eq!(points_seen.len(), 100);
     }
+
+    #[test]
+    fn test_sample_point_always_contained() {
+        let region = Region::new(&[
+            Interval::try_new(1.0, 5.0).unwrap(),
+            Interval::try_new(1.0, 1.0 + f64::EPSILON).unwrap(),
+        ]);
+
+        use rand::{SeedableRng, rngs::StdRng};
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..10_000 {
+            let point = region.sample_point(&mut rng);
+            assert!(region.contains(&point));
+        }
+    }
 }
@@ -0,0 +1,71 @@
+use std::{
+    fmt::{Debug, Display},
+    iter::Sum,
+};
+
+use approx::AbsDiffEq;
+use num_traits::float::FloatCore;
+use rand::distr::uniform::SampleUniform;
+
+/// The floating point type used for coordinates, implemented for [f32] and [f64].
+/// Everything defaults to [f64], an [f32] tree halves the memory used by its points.
+pub trait Scalar:
+    FloatCore
+    + AbsDiffEq<Epsilon = Self>
+    + Debug
+    + Display
+    + Default
+    + Sum
+    + SampleUniform
+    + Into<f64>
+    + Send
+    + Sync
+    + 'static
+{
+    /// Convert from an [f64], rounding to the nearest representable value
+    fn from_f64(value: f64) -> Self;
+
+    fn sqrt(self) -> Self;
+
+    /// The smallest value greater than `self`, see [f64::next_up]
+    fn next_up(self) -> Self;
+
+    /// The value halfway between `self` and `other`, without overflowing, see [f64::midpoint]
+    fn midpoint(self, other: Self) -> Self;
+}
+
+impl Scalar for f32 {
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn sqrt(self) -> Self {
+        f32::sqrt(self)
+    }
+
+    fn next_up(self) -> Self {
+        f32::next_up(self)
+    }
+
+    fn midpoint(self, other: Self) -> Self {
+        f32::midpoint(self, other)
+    }
+}
+
+impl Scalar for f64 {
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+
+    fn next_up(self) -> Self {
+        f64::next_up(self)
+    }
+
+    fn midpoint(self, other: Self) -> Self {
+        f64::midpoint(self, other)
+    }
+}
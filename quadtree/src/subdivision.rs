@@ -0,0 +1,80 @@
+use crate::{region::Region, scalar::Scalar};
+use ordered_float::OrderedFloat;
+
+/// How a [crate::QuadTree] node splits its region into child regions when it overflows.
+/// Strategies are chosen by type, e.g. `QuadTree<N, V, SplitLongestAxis>`, see
+/// [crate::quadtree::QuadTreeBuilder::strategy].
+pub trait SubdivisionStrategy<const N: usize, F: Scalar = f64> {
+    /// The child regions, which should cover `region` without overlapping
+    fn subdivide(region: &Region<N, F>) -> Vec<Region<N, F>>;
+}
+
+/// Split every axis in half, giving 2^N children. This is the classic quadtree/octree split.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SplitAllAxes;
+
+/// Split only the longest axis in half, giving 2 children like a kd-tree.
+/// Keeps the number of children constant for high dimensional data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SplitLongestAxis;
+
+impl<const N: usize, F: Scalar> SubdivisionStrategy<N, F> for SplitAllAxes {
+    fn subdivide(region: &Region<N, F>) -> Vec<Region<N, F>> {
+        region.subdivide_iter().collect()
+    }
+}
+
+impl<const N: usize, F: Scalar> SubdivisionStrategy<N, F> for SplitLongestAxis {
+    fn subdivide(region: &Region<N, F>) -> Vec<Region<N, F>> {
+        let extents = region.extents();
+        let Some(axis) = (0..N).max_by_key(|&axis| OrderedFloat(extents[axis])) else {
+            return vec![region.clone()];
+        };
+        region.intervals()[axis]
+            .subdivide()
+            .into_iter()
+            .map(|half| {
+                let mut intervals = *region.intervals();
+                intervals[axis] = half;
+                Region::new(&intervals)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interval::Interval;
+
+    #[test]
+    fn test_split_all_axes() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 1.0).unwrap(),
+        ]);
+        assert_eq!(SplitAllAxes::subdivide(&region).len(), 4);
+    }
+
+    #[test]
+    fn test_split_longest_axis() {
+        let region = Region::new(&[
+            Interval::try_new(0.0, 100.0).unwrap(),
+            Interval::try_new(0.0, 1.0).unwrap(),
+        ]);
+        let children = SplitLongestAxis::subdivide(&region);
+        assert_eq!(
+            children,
+            vec![
+                Region::new(&[
+                    Interval::try_new(0.0, 50.0).unwrap(),
+                    Interval::try_new(0.0, 1.0).unwrap(),
+                ]),
+                Region::new(&[
+                    Interval::try_new(50.0, 100.0).unwrap(),
+                    Interval::try_new(0.0, 1.0).unwrap(),
+                ]),
+            ]
+        );
+    }
+}
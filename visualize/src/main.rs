@@ -56,8 +56,7 @@ fn raw_window_event(app: &App, model: &mut Model, event: &WindowEvent) {
 }
 
 fn update(_app: &App, model: &mut Model, _update: Update) {
-    let egui = &mut model.egui;
-    let _ctx = egui.begin_frame();
+    model.update_ui();
 }
 
 fn view(app: &App, model: &Model, frame: Frame) {
@@ -7,7 +7,7 @@ use eyre::Result;
 
 use nannou::Draw;
 use nannou::geom::Rect;
-use nannou_egui::Egui;
+use nannou_egui::{Egui, egui};
 use quadtree::QuadTree;
 use quadtree::point::Point;
 use quadtree::region::Region;
@@ -16,36 +16,90 @@ use rand::rngs::StdRng;
 
 use nannou::prelude::*;
 
-const RADIUS: f32 = 100.0;
+const DEFAULT_RADIUS: f32 = 100.0;
+const DEFAULT_MAX_POINTS: usize = 2;
 const DOT_SIZE: f32 = 7.5;
 type Point2D = Point<2>;
 
+/// How the points inside the query circle are found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Technique {
+    /// Check every point against the query
+    Cartesian,
+    /// Query a [QuadTree] built from the points
+    Quadtree,
+}
+
 pub struct Model {
     pub egui: Egui,
     points: Vec<Point2D>,
     mouse_position: Option<Point2D>,
     region: Region<2>,
     rng: StdRng,
+    technique: Technique,
+    radius: f32,
+    max_points: usize,
+    /// Built from `points`, kept up to date as points are added so it isn't rebuilt every frame
+    quadtree: QuadTree<2, Point2D>,
 }
 
 impl Model {
     pub fn try_new(egui: Egui, rect: Rect) -> Result<Self> {
         // Create a region from the Rect
         let region: Region<2> = rect.into();
+        let max_points = NonZeroUsize::new(DEFAULT_MAX_POINTS).expect("default is non-zero");
+        let quadtree = QuadTree::new(&region, max_points);
         Ok(Self {
             egui,
             points: Vec::new(),
             mouse_position: None,
             region,
             rng: SeedableRng::seed_from_u64(42),
+            technique: Technique::Quadtree,
+            radius: DEFAULT_RADIUS,
+            max_points: DEFAULT_MAX_POINTS,
+            quadtree,
         })
     }
 
+    /// Build the egui settings panel for this frame
+    pub fn update_ui(&mut self) {
+        let max_points = self.max_points;
+        let mut cleared = false;
+        let ctx = self.egui.begin_frame();
+        egui::Window::new("Settings").show(&ctx, |ui| {
+            ui.label("Technique");
+            ui.radio_value(&mut self.technique, Technique::Cartesian, "Cartesian");
+            ui.radio_value(&mut self.technique, Technique::Quadtree, "Quadtree");
+            ui.add(egui::Slider::new(&mut self.radius, 10.0..=400.0).text("Radius"));
+            ui.add(egui::Slider::new(&mut self.max_points, 1..=64).text("Max points per node"));
+
+            ui.separator();
+            ui.label(format!("Points: {}", self.points.len()));
+            ui.label(format!(
+                "Depth: {}, Nodes: {}",
+                self.quadtree.depth(),
+                self.quadtree.node_count()
+            ));
+            if ui.button("Clear").clicked() {
+                self.points.clear();
+                cleared = true;
+            }
+        });
+        drop(ctx);
+        if cleared || self.max_points != max_points {
+            self.rebuild_quadtree();
+        }
+    }
+
     pub fn set_mouse_position(&mut self, position: Option<Point2D>) {
         self.mouse_position = position;
     }
 
     pub fn add_point(&mut self, point: Point2D) {
+        self.quadtree
+            .insert(point)
+            .expect("Inserting point into quadtree succeeds");
         self.points.push(point);
     }
 
@@ -56,32 +110,51 @@ impl Model {
         }
     }
 
-    fn quadtree(&self) -> QuadTree<2, Point2D> {
-        // Create a quadtree with a capacity of 2 points per region
-        let mut qt = QuadTree::new(&self.region, NonZeroUsize::new(2).expect("2 is non-zero"));
+    /// Rebuild the quadtree from scratch, e.g. after the capacity of points per region changes
+    fn rebuild_quadtree(&mut self) {
+        let max_points = NonZeroUsize::new(self.max_points).expect("slider starts at 1");
+        let mut qt = QuadTree::new(&self.region, max_points);
         for point in &self.points {
             // Insert points into the quadtree
             qt.insert(*point)
                 .expect("Inserting point into quadtree succeeds");
         }
-        qt
+        self.quadtree = qt;
+    }
+
+    /// Points within the query radius of `center`, using the selected [Technique]
+    fn points_within_distance(&self, center: &Point2D) -> HashSet<Point2D> {
+        match self.technique {
+            Technique::Quadtree => {
+                let distance_query = center.to_distance_based_query(self.radius as f64);
+                self.quadtree
+                    .query_cloned(&distance_query)
+                    .into_iter()
+                    .collect()
+            }
+            Technique::Cartesian => self
+                .points
+                .iter()
+                .filter(|point| point.distance(center) <= self.radius as f64)
+                .cloned()
+                .collect(),
+        }
     }
 
     pub fn draw_app(&self, draw: &Draw) {
-        let qt = self.quadtree();
+        let qt = (self.technique == Technique::Quadtree).then_some(&self.quadtree);
         // Draw circle around the mouse position if it exists, and find points within that circle.
         let points_inside_query = match &self.mouse_position {
             Some(mouse_pos) => {
                 let coords = mouse_pos.dimension_values();
                 draw.ellipse()
                     .x_y(coords[0] as f32, coords[1] as f32)
-                    .w_h(RADIUS * 2.0, RADIUS * 2.0)
+                    .w_h(self.radius * 2.0, self.radius * 2.0)
                     .stroke(GREEN)
                     .stroke_weight(2.0)
                     .no_fill();
 
-                let distance_query = &Point::new(coords).to_distance_based_query(RADIUS as f64);
-                qt.query(distance_query).cloned().collect()
+                self.points_within_distance(mouse_pos)
             }
             None => HashSet::new(),
         };
@@ -101,8 +174,8 @@ impl Model {
         }
 
         // Draw the quadtree regions
-        for region in qt.regions().iter() {
-            let rect: Rect = region.into();
+        for region in qt.iter().flat_map(|qt| qt.regions()) {
+            let rect: Rect = (&region).into();
             draw.rect()
                 .xy(rect.xy())
                 .wh(rect.wh())